@@ -0,0 +1,140 @@
+//`export`, `import` and `backup` modes of the binary, which take the listener database out or in.
+
+use std::{error::Error, path::Path};
+
+use chrono::{DateTime, Local};
+
+use super::database::{Database, DatabaseError};
+use super::stats::{self, Format, Table};
+
+//the exported tables and their columns
+const TABLES: &[(&str, &[&str])] = &[
+    (
+        "listeners",
+        &[
+            "id",
+            "name",
+            "tag",
+            "visit_count",
+            "stay_duration",
+            "legacy_visit_count",
+            "legacy_stay_duration",
+        ],
+    ),
+    ("names", &["listener_id", "name", "tag", "seen_at"]),
+    (
+        "milestones",
+        &["listener_id", "kind", "value", "achieved_at"],
+    ),
+    ("notes", &["listener_id", "content", "created_at"]),
+    ("listener_tags", &["listener_id", "tag", "value"]),
+    ("listener_voices", &["listener_id", "voice"]),
+    (
+        "pronunciations",
+        &["pattern", "reading", "is_regex", "created_at"],
+    ),
+    ("lives", &["id", "started_at", "ended_at"]),
+    (
+        "visits",
+        &["listener_id", "live_id", "joined_at", "left_at"],
+    ),
+    (
+        "gifts",
+        &[
+            "listener_id",
+            "live_id",
+            "kind",
+            "item",
+            "amount",
+            "combo",
+            "created_at",
+        ],
+    ),
+    (
+        "comments",
+        &["listener_id", "live_id", "message", "created_at"],
+    ),
+];
+
+//parses the arguments following `export` (e.g. `--format csv`)
+pub fn parse_format(args: &[String]) -> Result<Format, Box<dyn Error>> {
+    match args {
+        [] => Ok(Format::Json),
+        [option, value] if (option == "--format") => value.parse(),
+        _ => Err(format!(
+            "invalid arguments {:?} (expected `--format <format>`)",
+            args
+        )
+        .into()),
+    }
+}
+
+//dumps all the data as they are stored (durations in seconds and timestamps in UNIX time)
+pub fn export(database: &Database, format: Format) -> Result<String, DatabaseError> {
+    let tables = TABLES
+        .iter()
+        .map(|(name, columns)| {
+            Ok(Table {
+                name,
+                columns: columns.to_vec(),
+                rows: database.dump_table(name, columns)?,
+            })
+        })
+        .collect::<Result<_, DatabaseError>>()?;
+    Ok(stats::render(tables, format))
+}
+
+//the path of the backup file made at `now` in `dir`
+pub fn backup_path(dir: &str, now: DateTime<Local>) -> String {
+    Path::new(dir)
+        .join(format!("database_{}.sqlite3", now.format("%Y%m%d_%H%M%S")))
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::TimeZone;
+    use serde_json::Value;
+
+    use super::super::database::ListenerEntity;
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        db.insert(ListenerEntity::new(
+            1,
+            "Mike, Jr.".to_string(),
+            "mike".to_string(),
+            2,
+            Duration::from_secs(100),
+        ))
+        .unwrap();
+
+        let v: Value = serde_json::from_str(&export(&db, Format::Json).unwrap()).unwrap();
+        assert_eq!("Mike, Jr.", v["listeners"][0]["name"]);
+        assert_eq!(100, v["listeners"][0]["stay_duration"]);
+        assert_eq!(0, v["visits"].as_array().unwrap().len());
+
+        let s = export(&db, Format::Csv).unwrap();
+        assert!(s.contains("# listeners\nid,name,tag,visit_count,stay_duration,legacy_visit_count,legacy_stay_duration\n1,\"Mike, Jr.\",mike,2,100,2,100\n"));
+
+        assert_eq!(Format::Json, parse_format(&[]).unwrap());
+        assert_eq!(
+            Format::Csv,
+            parse_format(&["--format".to_string(), "csv".to_string()]).unwrap()
+        );
+        assert!(parse_format(&["--format".to_string()]).is_err());
+
+        let now = Local.with_ymd_and_hms(2023, 4, 5, 6, 7, 8).unwrap();
+        assert_eq!(
+            "/backup/database_20230405_060708.sqlite3",
+            backup_path("/backup", now)
+        );
+    }
+}
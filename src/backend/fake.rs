@@ -0,0 +1,105 @@
+use std::{
+    cell::{Cell, RefCell},
+    error::Error,
+};
+
+use super::super::listener::Listener;
+use super::{LiveStats, SpoonBackend};
+
+//in-memory backend for tests and offline tools
+//It records posted comments instead of sending them, and returns the listeners and the statistics set beforehand.
+#[derive(Default)]
+pub struct FakeBackend {
+    live_id: Cell<u64>,
+    comments: RefCell<Vec<String>>,
+    listeners: RefCell<Vec<Listener>>,
+    stats: RefCell<LiveStats>,
+    blocked_listener_ids: RefCell<Vec<usize>>,
+}
+
+impl FakeBackend {
+    pub fn new(live_id: u64) -> Self {
+        Self {
+            live_id: Cell::new(live_id),
+            ..Default::default()
+        }
+    }
+
+    //comments posted so far
+    pub fn comments(&self) -> Vec<String> {
+        self.comments.borrow().clone()
+    }
+
+    pub fn clear_comments(&self) {
+        self.comments.borrow_mut().clear();
+    }
+
+    pub fn set_listeners(&self, listeners: Vec<Listener>) {
+        *self.listeners.borrow_mut() = listeners;
+    }
+
+    pub fn set_stats(&self, stats: LiveStats) {
+        *self.stats.borrow_mut() = stats;
+    }
+
+    //listeners blocked so far
+    pub fn blocked_listener_ids(&self) -> Vec<usize> {
+        self.blocked_listener_ids.borrow().clone()
+    }
+}
+
+impl SpoonBackend for FakeBackend {
+    fn login(&self, _: &str, _: &str, _: &str) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn prepare_live(
+        &self,
+        _: &str,
+        _: &str,
+        _: &str,
+        _: &[String],
+        _: &str,
+        _: Option<&str>,
+    ) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn start_live(&self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn update_live_id(&self) -> Result<u64, Box<dyn Error>> {
+        Ok(self.live_id.get())
+    }
+
+    fn live_id(&self) -> u64 {
+        self.live_id.get()
+    }
+
+    fn post_comment(&self, s: &str) -> Result<(), Box<dyn Error>> {
+        self.comments.borrow_mut().push(s.to_string());
+        Ok(())
+    }
+
+    fn access_token(&self) -> Result<String, Box<dyn Error>> {
+        Err("`FakeBackend` has no access token".into())
+    }
+
+    fn retrieve_listeners(&self) -> Result<Vec<Listener>, Box<dyn Error>> {
+        Ok(self.listeners.borrow().clone())
+    }
+
+    fn retrieve_stats(&self) -> Result<LiveStats, Box<dyn Error>> {
+        Ok(self.stats.borrow().clone())
+    }
+
+    fn block_listener(&self, listener_id: usize) -> Result<(), Box<dyn Error>> {
+        self.blocked_listener_ids.borrow_mut().push(listener_id);
+        Ok(())
+    }
+
+    fn close(&self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+mod fake;
+
+use std::error::Error;
+
+use super::listener::Listener;
+
+pub use fake::FakeBackend;
+
+//statistics shown in the header of a live page
+//Each value is kept as displayed (e.g. `"1.2K"`) and is `"?"` when unavailable.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LiveStats {
+    pub timestamp: String, //elapsed time such as `01:23:45`
+    pub ranking: String,
+    pub num_spoon: String,
+    pub num_heart: String,
+    pub num_current_listener: String,
+    pub num_total_listener: String,
+}
+
+//everything the bot does on Spoon except for receiving events via WebSocket
+//`spoon_core::Spoon` implements this by driving a browser, and `FakeBackend` does in memory.
+pub trait SpoonBackend {
+    fn login(
+        &self,
+        url: &str,
+        twitter_id: &str,
+        twitter_password: &str,
+    ) -> Result<(), Box<dyn Error>>;
+
+    fn prepare_live(
+        &self,
+        start_url: &str,
+        genre: &str,
+        title: &str,
+        tags: &[String],
+        pinned_comment: &str,
+        bg_image: Option<&str>,
+    ) -> Result<(), Box<dyn Error>>;
+
+    fn start_live(&self) -> Result<(), Box<dyn Error>>;
+
+    //looks up the id of the current live and remembers it
+    fn update_live_id(&self) -> Result<u64, Box<dyn Error>>;
+
+    fn live_id(&self) -> u64;
+
+    fn post_comment(&self, s: &str) -> Result<(), Box<dyn Error>>;
+
+    //the access token of the logged-in user, used to post comments via WebSocket
+    fn access_token(&self) -> Result<String, Box<dyn Error>>;
+
+    //retrieves the list of current listeners
+    fn retrieve_listeners(&self) -> Result<Vec<Listener>, Box<dyn Error>>;
+
+    fn retrieve_stats(&self) -> Result<LiveStats, Box<dyn Error>>;
+
+    //blocks the listener from the current live
+    fn block_listener(&self, listener_id: usize) -> Result<(), Box<dyn Error>>;
+
+    fn close(&self) -> Result<(), Box<dyn Error>>;
+}
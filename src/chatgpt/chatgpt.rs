@@ -21,6 +21,7 @@ async fn caller(
     client: Arc<Client>,
 ) {
     let start = Instant::now();
+    #[allow(clippy::redundant_closure_call)]
     let res = (|| async {
         let mut has_retried = false;
         loop {
@@ -0,0 +1,34 @@
+use std::error::Error;
+
+use rand::seq::SliceRandom;
+
+use super::super::player::{Audio, AudioEffect};
+use super::{Command, CommandContext};
+
+//adds a random BGM to the playlist
+pub struct BgmCommand;
+
+impl Command for BgmCommand {
+    fn name(&self) -> &str {
+        "bgm"
+    }
+
+    fn execute(&self, ctx: &mut CommandContext, _args: &[&str]) -> Result<(), Box<dyn Error>> {
+        //The first one is the BGM played from the beginning.
+        if (ctx.config.spoon.live.bgm.audio_list.len() <= 1) {
+            let s = ctx.render("bgm_failed", &[]);
+            ctx.reply(&s);
+            ctx.say(&s);
+            return Ok(());
+        }
+        let audio_list = &ctx.config.spoon.live.bgm.audio_list[1..];
+        let bgm = audio_list.choose(ctx.rng).unwrap();
+        let audio = Audio::new(&bgm.path, bgm.volume, AudioEffect::default());
+        ctx.bgm.push(&audio);
+        let s = ctx.render("bgm_added", &[("title", &bgm.title)]);
+        ctx.reply(&s);
+        let s = ctx.render("bgm_added_voice", &[]);
+        ctx.say(&s);
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use std::error::Error;
+
+use itertools::Itertools;
+use rand::Rng;
+
+use super::{Command, CommandContext};
+
+pub struct FortuneCommand;
+
+impl Command for FortuneCommand {
+    fn name(&self) -> &str {
+        "fortune"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["omikuji"]
+    }
+
+    fn execute(&self, ctx: &mut CommandContext, _args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let stars = (0..3)
+            .map(|_| "★".repeat(ctx.rng.gen_range(1..=5)))
+            .collect_vec();
+        let number = ctx.rng.gen_range(0..=1000).to_string();
+        let color = ctx.render("fortune_color", &[]);
+
+        let s = ctx.render(
+            "fortune",
+            &[
+                ("nickname", &ctx.user.nickname),
+                ("overall", &stars[0]),
+                ("love", &stars[1]),
+                ("money", &stars[2]),
+                ("number", &number),
+                ("color", &color),
+            ],
+        );
+        ctx.reply(&s);
+        Ok(())
+    }
+}
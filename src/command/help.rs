@@ -0,0 +1,41 @@
+use std::error::Error;
+
+use itertools::Itertools;
+
+use super::{Command, CommandContext, Permission};
+
+//lists the commands the user can use
+pub struct HelpCommand;
+
+impl Command for HelpCommand {
+    fn name(&self) -> &str {
+        "help"
+    }
+
+    fn execute(&self, ctx: &mut CommandContext, _args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let permission = Permission::of(ctx.user);
+        let commands = ctx
+            .registry
+            .commands()
+            .filter(|c| c.permission() <= permission)
+            .map(|c| {
+                let names = [c.name()]
+                    .into_iter()
+                    .chain(c.aliases().iter().copied())
+                    .map(|name| format!("/{}", name))
+                    .join(" ");
+                format!("{}: {}", names, c.help(ctx))
+            })
+            .join("\n");
+        let voices = ctx
+            .config
+            .voicevox
+            .voices
+            .iter()
+            .map(|preset| &preset.name)
+            .join(", ");
+        let s = ctx.render("help", &[("commands", &commands), ("voices", &voices)]);
+        ctx.reply(&s);
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+//Chat commands such as `/bgm`.
+//Each command implements `Command` and is registered to `CommandRegistry`, from which `/help` is generated.
+//Effect and speaker modifiers such as `/echo` and `/zundamon` are not commands but are handled in `modifier`.
+
+mod bgm;
+mod fortune;
+mod help;
+pub mod modifier;
+mod myvoice;
+mod rank;
+mod ranking;
+mod whois;
+
+use std::{collections::HashMap, error::Error, time::Instant};
+
+use rand::rngs::ThreadRng;
+
+use super::bgm::BGM;
+use super::config::{Config, VoicePreset};
+use super::database::Database;
+use super::listener::Listener;
+use super::locale::Locale;
+use super::message_catalog::MessageCatalog;
+use super::models::live_message::User;
+use super::player::AudioEffect;
+use super::voicevox::{Script, VoiceVox};
+
+pub use bgm::BgmCommand;
+pub use fortune::FortuneCommand;
+pub use help::HelpCommand;
+pub use myvoice::{preset_of_listener, MyVoiceCommand};
+pub use rank::RankCommand;
+pub use ranking::RankingCommand;
+pub use whois::WhoisCommand;
+
+//who can use a command
+//Variants are ordered from the least privileged one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Permission {
+    Everyone,
+    Manager, //fixed managers of the live
+    Dj,      //the owner of the live
+}
+
+impl Permission {
+    pub fn of(user: &User) -> Self {
+        if (user.is_dj) {
+            Permission::Dj
+        } else if (user.is_fixedmng) {
+            Permission::Manager
+        } else {
+            Permission::Everyone
+        }
+    }
+}
+
+//everything a command can access while it is executed
+pub struct CommandContext<'a> {
+    pub config: &'a Config,
+    pub messages: &'a MessageCatalog,
+    pub locale: Locale, //the locale of the user who sent the command
+    pub registry: &'a CommandRegistry,
+    pub database: &'a Database,
+    pub voicevox: &'a mut VoiceVox,
+    pub bgm: &'a BGM,
+    pub rng: &'a mut ThreadRng,
+    pub listeners: &'a HashMap<Listener, Instant>, //current listeners and when they joined
+    pub user: &'a User,
+    pub replies: Vec<String>,
+}
+
+impl CommandContext<'_> {
+    pub fn render(&self, key: &str, params: &[(&str, &str)]) -> String {
+        self.messages.render_in(self.locale, key, params)
+    }
+
+    //posts `s` as a comment
+    pub fn reply(&mut self, s: &str) {
+        self.replies.push(s.to_string());
+    }
+
+    //reads `s` aloud with the default speaker
+    pub fn say(&mut self, s: &str) {
+        if (self.config.voicevox.enabled) {
+            self.voicevox.say(Script::new(
+                s,
+                AudioEffect::default(),
+                self.config.voicevox.speaker,
+            ));
+        }
+    }
+}
+
+pub trait Command {
+    //the name without the leading slash (e.g. `bgm` for `/bgm`)
+    fn name(&self) -> &str;
+
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    //the one-line description shown in `/help`
+    fn help(&self, ctx: &CommandContext) -> String {
+        ctx.render(&format!("help_{}", self.name()), &[])
+    }
+
+    fn permission(&self) -> Permission {
+        Permission::Everyone
+    }
+
+    //`args` are the whitespace-separated tokens following the command
+    fn execute(&self, ctx: &mut CommandContext, args: &[&str]) -> Result<(), Box<dyn Error>>;
+}
+
+pub struct CommandRegistry {
+    commands: Vec<Box<dyn Command>>,
+}
+
+impl CommandRegistry {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self { commands: vec![] }
+    }
+
+    //the registry with all the built-in commands
+    pub fn with_default_commands() -> Self {
+        let mut ret = Self::new();
+        ret.register(Box::new(HelpCommand));
+        ret.register(Box::new(BgmCommand));
+        ret.register(Box::new(FortuneCommand));
+        ret.register(Box::new(RankCommand));
+        ret.register(Box::new(RankingCommand));
+        ret.register(Box::new(WhoisCommand));
+        ret.register(Box::new(MyVoiceCommand));
+        ret
+    }
+
+    pub fn register(&mut self, command: Box<dyn Command>) {
+        let names = [command.name()]
+            .into_iter()
+            .chain(command.aliases().iter().copied());
+        for name in names {
+            assert!(
+                self.find(name).is_none(),
+                "`/{}` is already registered",
+                name
+            );
+        }
+        self.commands.push(command);
+    }
+
+    //`name` is without the leading slash
+    pub fn find(&self, name: &str) -> Option<&dyn Command> {
+        self.commands
+            .iter()
+            .find(|c| c.name() == name || c.aliases().contains(&name))
+            .map(|c| c.as_ref())
+    }
+
+    //in the order of registration
+    pub fn commands(&self) -> impl Iterator<Item = &dyn Command> {
+        self.commands.iter().map(|c| c.as_ref())
+    }
+
+    //checks that every voice can be used as a modifier
+    //A voice named after a command or an effect would be silently shadowed by it.
+    pub fn validate_voices(&self, voices: &[VoicePreset]) -> Result<(), String> {
+        for (i, voice) in voices.iter().enumerate() {
+            let name = voice.name.as_str();
+            if (self.find(name).is_some()) {
+                return Err(format!("the voice `{}` is named after a command", name));
+            }
+            if (modifier::apply_effect(name, &mut AudioEffect::default())) {
+                return Err(format!("the voice `{}` is named after an effect", name));
+            }
+            if (voices[..i].iter().any(|v| v.name == name)) {
+                return Err(format!("the voice `{}` is defined twice", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct DiceCommand;
+
+    impl Command for DiceCommand {
+        fn name(&self) -> &str {
+            "dice"
+        }
+
+        fn aliases(&self) -> &[&str] {
+            &["saikoro"]
+        }
+
+        fn execute(&self, ctx: &mut CommandContext, _args: &[&str]) -> Result<(), Box<dyn Error>> {
+            ctx.reply("6");
+            Ok(())
+        }
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let mut registry = CommandRegistry::with_default_commands();
+        assert!(registry.find("dice").is_none());
+        registry.register(Box::new(DiceCommand));
+        assert_eq!("dice", registry.find("dice").unwrap().name());
+        assert_eq!("dice", registry.find("saikoro").unwrap().name());
+        assert_eq!("bgm", registry.find("bgm").unwrap().name());
+        assert_eq!("dice", registry.commands().last().unwrap().name());
+    }
+
+    #[test]
+    // #[ignore]
+    #[should_panic]
+    fn test02() {
+        let mut registry = CommandRegistry::with_default_commands();
+        registry.register(Box::new(DiceCommand));
+        registry.register(Box::new(DiceCommand));
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        let mut user = User::default();
+        assert_eq!(Permission::Everyone, Permission::of(&user));
+        user.is_fixedmng = true;
+        assert_eq!(Permission::Manager, Permission::of(&user));
+        user.is_dj = true;
+        assert_eq!(Permission::Dj, Permission::of(&user));
+        assert!(Permission::Manager < Permission::Dj);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test04() {
+        let registry = CommandRegistry::with_default_commands();
+        let voices = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| VoicePreset {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert!(registry
+            .validate_voices(&voices(&["zundamon", "sayo"]))
+            .is_ok());
+        for names in [&["sayo", "help"][..], &["echo"], &["sayo", "sayo"]] {
+            assert!(registry.validate_voices(&voices(names)).is_err());
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use super::super::config::VoicePreset;
+use super::super::player::AudioEffect;
+
+//`name` is without the leading slash
+pub fn preset_of<'a>(presets: &'a [VoicePreset], name: &str) -> Option<&'a VoicePreset> {
+    presets.iter().find(|preset| preset.name == name)
+}
+
+//applies an effect such as `echo` (without the leading slash)
+//This returns `false` if `name` is not an effect.
+pub fn apply_effect(name: &str, effect: &mut AudioEffect) -> bool {
+    match name {
+        "reverb" => effect.reverb = true,
+        "echo" => effect.reverb = true, //same as `reverb`
+        "high" => effect.high = true,
+        "low" => effect.low = true,
+        "left" => effect.left = true,   //low quality on Linux
+        "right" => effect.right = true, //low quality on Linux
+        "fast" => effect.fast = true,
+        "slow" => effect.slow = true,
+        _ => return false,
+    }
+    true
+}
+
+//applies a modifier token such as `/echo` or `/zundamon` placed before a comment read aloud
+//The voices are defined in `config.voicevox.voices`, whose default effects are left to the caller
+// so that they are applied only to the voice finally chosen.
+//This returns `false` if `token` is not a modifier.
+pub fn apply<'a>(
+    token: &str,
+    presets: &'a [VoicePreset],
+    effect: &mut AudioEffect,
+    preset: &mut Option<&'a VoicePreset>,
+) -> bool {
+    let name = match token.strip_prefix('/') {
+        None => return false,
+        Some(s) => s,
+    };
+    if (apply_effect(name, effect)) {
+        return true;
+    }
+    match preset_of(presets, name) {
+        None => false,
+        Some(p) => {
+            *preset = Some(p);
+            true
+        }
+    }
+}
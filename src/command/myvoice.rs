@@ -0,0 +1,111 @@
+use std::error::Error;
+
+use itertools::Itertools;
+use log::error;
+
+use super::super::config::{self, VoicePreset};
+use super::super::database::Database;
+use super::modifier;
+use super::{Command, CommandContext};
+
+//lets the user choose the voice their comments are read aloud in (e.g. `/myvoice zundamon`)
+pub struct MyVoiceCommand;
+
+impl Command for MyVoiceCommand {
+    fn name(&self) -> &str {
+        "myvoice"
+    }
+
+    fn execute(&self, ctx: &mut CommandContext, args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let id = ctx.user.id as usize;
+        let presets = &ctx.config.voicevox.voices;
+        let voices = presets.iter().map(|preset| &preset.name).join(", ");
+        let s = match args {
+            ["reset"] => {
+                ctx.database.remove_voice(id)?;
+                ctx.render("myvoice_reset", &[("nickname", &ctx.user.nickname)])
+            }
+            [voice] if (modifier::preset_of(presets, voice).is_some()) => {
+                ctx.database.set_voice(id, voice)?;
+                ctx.render(
+                    "myvoice_set",
+                    &[("nickname", &ctx.user.nickname), ("voice", voice)],
+                )
+            }
+            [voice] => ctx.render(
+                "myvoice_not_found",
+                &[("voice", voice), ("voices", &voices)],
+            ),
+            _ => ctx.render("myvoice_usage", &[("voices", &voices)]),
+        };
+        ctx.reply(&s);
+        Ok(())
+    }
+}
+
+//a stable hash so that the same listener always gets the same voice
+fn hash_id(id: usize) -> usize {
+    ((id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32) as usize
+}
+
+//the voice the comments of the listener are read aloud in:
+// the one chosen via `/myvoice`, the one assigned by the user id, or `None` (the default speaker), in this order of priority
+pub fn preset_of_listener<'a>(
+    config: &'a config::VoiceVox,
+    database: &Database,
+    listener_id: usize,
+) -> Option<&'a VoicePreset> {
+    let chosen = database.select_voice(listener_id).unwrap_or_else(|e| {
+        error!("{}", e);
+        None
+    });
+    let assigned = (!config.auto_voices.is_empty())
+        .then(|| config.auto_voices[hash_id(listener_id) % config.auto_voices.len()].clone());
+    chosen
+        .into_iter()
+        .chain(assigned)
+        .find_map(|voice| modifier::preset_of(&config.voices, &voice))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        let preset = |name: &str, speaker: usize| VoicePreset {
+            name: name.to_string(),
+            speaker,
+            ..Default::default()
+        };
+        let mut config = config::VoiceVox {
+            speaker: 2,
+            voices: vec![
+                preset("zundamon", 3),
+                preset("asmr", 22),
+                preset("sayo", 46),
+            ],
+            ..Default::default()
+        };
+        let speaker_of_listener = |config: &config::VoiceVox, db: &Database, id: usize| {
+            preset_of_listener(config, db, id).map_or(config.speaker, |preset| preset.speaker)
+        };
+        assert_eq!(2, speaker_of_listener(&config, &db, 1));
+
+        config.auto_voices = vec!["zundamon".to_string(), "sayo".to_string()];
+        let speakers = (1..=100)
+            .map(|id| speaker_of_listener(&config, &db, id))
+            .collect::<Vec<_>>();
+        assert!(speakers.iter().all(|s| [3, 46].contains(s)));
+        assert!(speakers.contains(&3) && speakers.contains(&46));
+        assert_eq!(speakers[0], speaker_of_listener(&config, &db, 1));
+
+        db.set_voice(1, "asmr").unwrap();
+        assert_eq!(22, speaker_of_listener(&config, &db, 1));
+        //e.g. removed from the presets
+        db.set_voice(1, "unknown").unwrap();
+        assert_eq!(speakers[0], speaker_of_listener(&config, &db, 1));
+    }
+}
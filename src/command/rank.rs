@@ -0,0 +1,49 @@
+use std::error::Error;
+
+use super::{Command, CommandContext};
+
+//shows the rank of the user
+pub struct RankCommand;
+
+impl Command for RankCommand {
+    fn name(&self) -> &str {
+        "rank"
+    }
+
+    fn execute(&self, ctx: &mut CommandContext, _args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let id = ctx.user.id as usize;
+
+        let (entity, rank) = match (
+            ctx.database.select_by_id(id)?,
+            ctx.database.select_rank(id)?,
+        ) {
+            (Some(entity), Some(rank)) => (entity, rank),
+            _ => return Err(format!("user {} is not found in the database", id).into()),
+        };
+
+        //the stay duration in the current live is not yet saved in the database
+        let elapsed = ctx
+            .listeners
+            .iter()
+            .find(|(k, _)| k.id == id)
+            .map(|(_, t)| t.elapsed())
+            .unwrap_or_default();
+
+        let s = ctx.render(
+            "rank",
+            &[
+                ("nickname", &ctx.user.nickname),
+                ("rank", &rank.to_string()),
+                ("num_listeners", &ctx.database.count()?.to_string()),
+                (
+                    "stay",
+                    &ctx.locale
+                        .pretty_print_duration(entity.stay_duration + elapsed),
+                ),
+                ("visit_count", &entity.visit_count.to_string()),
+            ],
+        );
+        ctx.reply(&s);
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use std::error::Error;
+
+use itertools::Itertools;
+use regex::Regex;
+
+use super::{Command, CommandContext};
+
+//shows the top 5 listeners
+pub struct RankingCommand;
+
+impl Command for RankingCommand {
+    fn name(&self) -> &str {
+        "ranking"
+    }
+
+    fn execute(&self, ctx: &mut CommandContext, _args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let ranker = ctx.database.select_top(5)?;
+
+        //omits seconds
+        let re = Regex::new(&format!(r#"\d+{}"#, ctx.locale.duration_units().2)).unwrap();
+
+        let entries = ranker
+            .into_iter()
+            .enumerate()
+            .map(|(i, e)| {
+                ctx.render(
+                    "ranking_entry",
+                    &[
+                        ("rank", &(i + 1).to_string()),
+                        (
+                            "stay",
+                            &re.replace(&ctx.locale.pretty_print_duration(e.stay_duration), ""),
+                        ),
+                        ("visit_count", &e.visit_count.to_string()),
+                    ],
+                )
+            })
+            .join("\n");
+        let s = ctx.render("ranking", &[("entries", &entries)]);
+        ctx.reply(&s);
+        Ok(())
+    }
+}
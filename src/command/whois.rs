@@ -0,0 +1,69 @@
+use std::error::Error;
+
+use itertools::Itertools;
+
+use super::{Command, CommandContext, Permission};
+
+//the maximum number of the listeners shown at once
+const MAX_RESULTS: usize = 3;
+
+//looks up a listener by id, nickname or tag, showing the past names
+//This is for tracing a troll who frequently changes the name.
+pub struct WhoisCommand;
+
+impl Command for WhoisCommand {
+    fn name(&self) -> &str {
+        "whois"
+    }
+
+    fn permission(&self) -> Permission {
+        Permission::Manager
+    }
+
+    fn execute(&self, ctx: &mut CommandContext, args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let keyword = args.join(" ");
+        let keyword = keyword.trim_start_matches('@');
+        if (keyword.is_empty()) {
+            let s = ctx.render("whois_usage", &[]);
+            ctx.reply(&s);
+            return Ok(());
+        }
+
+        let mut ids = ctx.database.select_listener_ids_by_name(keyword)?;
+        if let Ok(id) = keyword.parse::<usize>() {
+            if (ctx.database.select_by_id(id)?.is_some() && !ids.contains(&id)) {
+                ids.insert(0, id);
+            }
+        }
+        if (ids.is_empty()) {
+            let s = ctx.render("whois_not_found", &[("keyword", keyword)]);
+            ctx.reply(&s);
+            return Ok(());
+        }
+
+        for id in ids.into_iter().take(MAX_RESULTS) {
+            let entity = match ctx.database.select_by_id(id)? {
+                None => continue,
+                Some(e) => e,
+            };
+            let names = ctx
+                .database
+                .select_names(id)?
+                .into_iter()
+                .map(|e| format!("{}(@{})", e.name, e.tag))
+                .join(" → ");
+            let s = ctx.render(
+                "whois",
+                &[
+                    ("nickname", &entity.name),
+                    ("tag", &entity.tag),
+                    ("id", &id.to_string()),
+                    ("visit_count", &entity.visit_count.to_string()),
+                    ("names", &names),
+                ],
+            );
+            ctx.reply(&s);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,    //automatic greetings and thanks
+    Normal, //replies to listeners
+    High,   //messages from the operator, notices
+}
+
+//at most how many listeners a grouped comment names, so that it fits in a comment
+const MAX_GROUP_SIZE: usize = 10;
+
+enum Body {
+    Text(String),
+    //`template` contains `{nickname}`, which is replaced with `nicknames` joined by `separator` when popped
+    Grouped {
+        group: String,
+        template: String,
+        separator: String,
+        nicknames: Vec<String>,
+    },
+}
+
+impl Body {
+    fn render(self) -> String {
+        match self {
+            Body::Text(s) => s,
+            Body::Grouped {
+                template,
+                separator,
+                nicknames,
+                ..
+            } => template.replace("{nickname}", &nicknames.join(&separator)),
+        }
+    }
+}
+
+struct Entry {
+    body: Body,
+    priority: Priority,
+}
+
+//This struct schedules outgoing comments.
+//Comments are posted one by one at least `interval` apart, higher priority first (and first in, first out within the same priority).
+//A comment identical to one still waiting is coalesced into it, so that a burst of the same message is posted only once.
+//Likewise, a message naming a listener (e.g. thanks for a heart) is merged with the waiting one of the same group,
+// so that a burst of hearts from different listeners is posted as a single comment naming all of them.
+pub struct CommentQueue {
+    entries: VecDeque<Entry>,
+    interval: Duration,
+    last_popped: Option<Instant>,
+}
+
+impl CommentQueue {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            interval,
+            last_popped: None,
+        }
+    }
+
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn push(&mut self, text: &str, priority: Priority) {
+        if let Some(e) = self
+            .entries
+            .iter_mut()
+            .find(|e| matches!(&e.body, Body::Text(s) if (s == text)))
+        {
+            e.priority = e.priority.max(priority);
+            return;
+        }
+        self.entries.push_back(Entry {
+            body: Body::Text(text.to_string()),
+            priority,
+        });
+    }
+
+    //queues `template` (e.g. `{nickname}さん、ハートありがとう。`) naming `nickname`
+    //`group` identifies the message (e.g. the message key and the locale), and `separator` joins the nicknames when merged.
+    pub fn push_grouped(
+        &mut self,
+        group: &str,
+        template: &str,
+        separator: &str,
+        nickname: &str,
+        priority: Priority,
+    ) {
+        let waiting = self.entries.iter_mut().find(|e| {
+            matches!(&e.body, Body::Grouped { group: g, nicknames, .. }
+                if (g == group && (nicknames.len() < MAX_GROUP_SIZE || nicknames.iter().any(|n| n == nickname))))
+        });
+        if let Some(e) = waiting {
+            if let Body::Grouped { nicknames, .. } = &mut e.body {
+                if (!nicknames.iter().any(|n| n == nickname)) {
+                    nicknames.push(nickname.to_string());
+                }
+            }
+            e.priority = e.priority.max(priority);
+            return;
+        }
+        self.entries.push_back(Entry {
+            body: Body::Grouped {
+                group: group.to_string(),
+                template: template.to_string(),
+                separator: separator.to_string(),
+                nicknames: vec![nickname.to_string()],
+            },
+            priority,
+        });
+    }
+
+    //returns the next comment to post, or `None` if nothing is waiting or it's too early to post the next one
+    pub fn pop(&mut self) -> Option<String> {
+        self.pop_at(Instant::now())
+    }
+
+    fn pop_at(&mut self, now: Instant) -> Option<String> {
+        if let Some(t) = self.last_popped {
+            if (now.saturating_duration_since(t) < self.interval) {
+                return None;
+            }
+        }
+        let max_priority = self.entries.iter().map(|e| e.priority).max()?;
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.priority == max_priority)
+            .unwrap();
+        self.last_popped = Some(now);
+        self.entries.remove(index).map(|e| e.body.render())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let mut queue = CommentQueue::new(Duration::ZERO);
+        assert_eq!(None, queue.pop());
+        queue.push("a", Priority::Low);
+        queue.push("b", Priority::Normal);
+        queue.push("c", Priority::Low);
+        queue.push("d", Priority::High);
+        queue.push("e", Priority::Normal);
+        assert_eq!(5, queue.len());
+        assert_eq!(Some("d".to_string()), queue.pop());
+        assert_eq!(Some("b".to_string()), queue.pop());
+        assert_eq!(Some("e".to_string()), queue.pop());
+        assert_eq!(Some("a".to_string()), queue.pop());
+        assert_eq!(Some("c".to_string()), queue.pop());
+        assert_eq!(None, queue.pop());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let mut queue = CommentQueue::new(Duration::ZERO);
+        for _ in 0..10 {
+            queue.push("ハートありがとう。", Priority::Low);
+        }
+        queue.push("hello", Priority::Normal);
+        queue.push("ハートありがとう。", Priority::High);
+        assert_eq!(2, queue.len());
+        assert_eq!(Some("ハートありがとう。".to_string()), queue.pop());
+        assert_eq!(Some("hello".to_string()), queue.pop());
+        assert_eq!(None, queue.pop());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        let mut queue = CommentQueue::new(Duration::from_secs(1));
+        queue.push("a", Priority::Normal);
+        queue.push("b", Priority::Normal);
+        let now = Instant::now();
+        assert_eq!(Some("a".to_string()), queue.pop_at(now));
+        assert_eq!(None, queue.pop_at(now + Duration::from_millis(999)));
+        assert_eq!(
+            Some("b".to_string()),
+            queue.pop_at(now + Duration::from_millis(1000))
+        );
+
+        queue.set_interval(Duration::ZERO);
+        queue.push("c", Priority::Normal);
+        assert_eq!(
+            Some("c".to_string()),
+            queue.pop_at(now + Duration::from_millis(1000))
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test04() {
+        let mut queue = CommentQueue::new(Duration::ZERO);
+        let heart = "{nickname}さん、ハートありがとう。";
+        for nickname in ["A", "B", "A", "C"] {
+            queue.push_grouped("ja/heart", heart, "さん、", nickname, Priority::Low);
+        }
+        queue.push_grouped(
+            "en/heart",
+            "Thank you for the heart, {nickname}!",
+            ", ",
+            "D",
+            Priority::Low,
+        );
+        queue.push("hello", Priority::Normal);
+        assert_eq!(3, queue.len());
+        assert_eq!(Some("hello".to_string()), queue.pop());
+        assert_eq!(
+            Some("Aさん、Bさん、Cさん、ハートありがとう。".to_string()),
+            queue.pop()
+        );
+        assert_eq!(Some("Thank you for the heart, D!".to_string()), queue.pop());
+
+        for i in 0..(MAX_GROUP_SIZE + 1) {
+            queue.push_grouped("ja/heart", heart, "さん、", &i.to_string(), Priority::Low);
+        }
+        assert_eq!(2, queue.len());
+        queue.pop();
+        assert_eq!(
+            Some(format!("{}さん、ハートありがとう。", MAX_GROUP_SIZE)),
+            queue.pop()
+        );
+    }
+}
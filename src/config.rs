@@ -2,17 +2,21 @@ use itertools::Itertools;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fs::File,
     io::{BufRead, BufReader},
 };
 
+use super::command::CommandRegistry;
+use super::locale::Locale;
 use super::util;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct Config {
     pub twitter: Twitter,
     pub spoon: Spoon,
     pub database_file: String,
+    pub backup_dir: String, //disabled when empty
     pub selenium: Selenium,
     pub forbidden_words: Vec<String>,
     pub voicevox: VoiceVox,
@@ -36,10 +40,44 @@ pub struct Spoon {
     pub should_comment_guide: bool,
     pub should_comment_block: bool,
     pub should_call_over: bool,
+    pub should_post_via_websocket: bool,
+    pub min_comment_interval_ms: u64,
     pub message_tunnel_file: String,
+    pub message_catalog_file: String, //the built-in catalog is used when empty
+    pub locale: Locale,
+    pub should_use_listener_locale: bool,
+    pub loyalty: Loyalty,
+    pub websocket: WebSocket,
     pub live: Live,
 }
 
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Loyalty {
+    pub enabled: bool,
+    pub tiers: Vec<Tier>,
+    pub visit_count_milestones: Vec<usize>,
+    pub stay_hours_milestones: Vec<u64>,
+    pub should_celebrate_first_spoon: bool,
+    pub stinger_file: String, //played over the BGM on a milestone (disabled when empty)
+    pub stinger_volume: f64,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Tier {
+    pub name: String,
+    pub min_stay_hours: u64,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct WebSocket {
+    pub capture_file: String, //disabled when empty
+    pub replay_speed: f64,    //used by the `replay` mode
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub heartbeat_interval_ms: u64, //disabled when `0`
+    pub should_comment_reconnection: bool,
+}
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct Live {
     pub enabled: bool,
@@ -77,16 +115,58 @@ pub struct Selenium {
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct VoiceVox {
     pub enabled: bool,
-    pub should_skip_non_japanese: bool,
-    pub should_use_google_speech_for_non_japanese: bool,
-    pub url: String,
-    pub api_key: String,
+    pub backend: VoiceVoxBackend,
+    pub routes: HashMap<Locale, TtsEngine>, //The text in a language not listed here is not read aloud.
+    pub url: String,                        //for `web_api`
+    pub api_key: String,                    //for `web_api`
+    pub engine: Engine,
     pub speaker: usize,
+    pub voices: Vec<VoicePreset>,
+    pub auto_voices: Vec<String>, //assigned to the listeners who have not chosen a voice via `/myvoice` (disabled when empty)
     pub speed: f64,
     pub output_dir: String,
     pub timeout_sec: u64,
 }
 
+//a named voice, which is used as a modifier (e.g. `/zundamon hello`) and via `/myvoice`
+//`speed` and `pitch` override `voicevox.speed` and `voicevox.engine.pitch_scale` if specified (`pitch` is ignored by the web API).
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct VoicePreset {
+    pub name: String,
+    pub speaker: usize,
+    pub speed: Option<f64>,
+    pub pitch: Option<f64>,
+    pub effects: Vec<String>, //the modifiers applied by default, without the leading slash (e.g. `reverb`)
+}
+
+//the text-to-speech engine the text in a language is routed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TtsEngine {
+    #[serde(rename = "voicevox")]
+    VoiceVox, //either of `VoiceVoxBackend`
+    GoogleSpeech,
+    Silent, //writes silent audio files without any engine (for testing)
+}
+
+//which implementation of VOICEVOX is used
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoiceVoxBackend {
+    #[default]
+    WebApi, //the web API of su-shiki.com
+    Engine, //a self-hosted VOICEVOX engine
+}
+
+//the parameters of a self-hosted VOICEVOX engine, which are passed to `/synthesis`
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Engine {
+    pub url: String, //e.g. `http://localhost:50021`
+    pub pitch_scale: f64,
+    pub intonation_scale: f64,
+    pub volume_scale: f64,
+}
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct ChatGPT {
     pub enabled: bool,
@@ -126,12 +206,30 @@ impl Config {
 
         let mut ret: Self = serde_json::from_str(&json_string).unwrap();
         util::canonicalize_path_in_place(&mut ret.spoon.message_tunnel_file);
+        if (!ret.spoon.message_catalog_file.is_empty()) {
+            util::canonicalize_path_in_place(&mut ret.spoon.message_catalog_file);
+        }
+        if (!ret.spoon.websocket.capture_file.is_empty()) {
+            ret.spoon.websocket.capture_file =
+                util::canonicalize_new_file_path(&ret.spoon.websocket.capture_file);
+        }
+        if (!ret.spoon.loyalty.stinger_file.is_empty()) {
+            util::canonicalize_path_in_place(&mut ret.spoon.loyalty.stinger_file);
+        }
         util::canonicalize_path_in_place(&mut ret.spoon.live.bg_image);
         ret.spoon.live.bgm.audio_list.iter_mut().for_each(|e| {
             util::canonicalize_path_in_place(&mut e.path);
         });
         util::canonicalize_path_in_place(&mut ret.voicevox.output_dir);
+        if (!ret.backup_dir.is_empty()) {
+            util::canonicalize_path_in_place(&mut ret.backup_dir);
+        }
         assert!(ret.spoon.live.tags.len() <= 5);
+        if let Err(e) =
+            CommandRegistry::with_default_commands().validate_voices(&ret.voicevox.voices)
+        {
+            panic!("invalid `voicevox.voices`: {}", e);
+        }
         ret.spoon.live.bgm.audio_list = ret
             .spoon
             .live
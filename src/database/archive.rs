@@ -0,0 +1,289 @@
+use std::{path::Path, time::Duration};
+
+use rusqlite::{backup::Backup, types::ValueRef, Connection, Statement};
+use serde_json::{json, Value};
+
+use super::{Database, DatabaseError};
+
+//the rows added by `merge()`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct MergeSummary {
+    pub num_new_listener: usize,
+    pub num_visit: usize,
+    pub num_comment: usize,
+    pub num_gift: usize,
+}
+
+impl Database {
+    //copies the whole database into `path` (overwritten if exists) while it is in use
+    pub fn backup(&self, path: &str) -> Result<(), DatabaseError> {
+        let mut dst = Connection::open(path)?;
+        let backup = Backup::new(&self.conn, &mut dst)?;
+        backup.run_to_completion(100, Duration::from_millis(50), None)?;
+        Ok(())
+    }
+
+    //the rows of the table as they are stored, in the order of `columns`
+    pub fn dump_table(
+        &self,
+        table: &str,
+        columns: &[&str],
+    ) -> Result<Vec<Vec<Value>>, DatabaseError> {
+        let mut statement: Statement = self.conn.prepare(&format!(
+            "SELECT {} FROM {} ORDER BY rowid;",
+            columns.join(", "),
+            table
+        ))?;
+        let ret = statement
+            .query_map([], |r| {
+                (0..columns.len())
+                    .map(|i| {
+                        Ok(match r.get_ref(i)? {
+                            ValueRef::Null => Value::Null,
+                            ValueRef::Integer(i) => json!(i),
+                            ValueRef::Real(f) => json!(f),
+                            ValueRef::Text(s) => json!(String::from_utf8_lossy(s)),
+                            ValueRef::Blob(_) => Value::Null,
+                        })
+                    })
+                    .collect()
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+
+    //merges another database (e.g. a co-host's one) into this one
+    //The other database is migrated to the latest version beforehand.
+    //The lives, visits, comments, gifts and names already in this database are skipped, so merging the same database twice is harmless.
+    //For the values accumulated before the visits were recorded (see the migration v3), the larger one is taken for the same reason.
+    pub fn merge(&self, path: &str) -> Result<MergeSummary, DatabaseError> {
+        if (!Path::new(path).exists()) {
+            return Err(DatabaseError::FileNotFound(path.to_string()));
+        }
+        drop(Database::new(Some(path))?);
+
+        self.conn.execute("ATTACH DATABASE ? AS other;", [path])?;
+        let ret = self.merge_attached();
+        self.conn.execute("DETACH DATABASE other;", [])?;
+        ret
+    }
+
+    fn merge_attached(&self) -> Result<MergeSummary, DatabaseError> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut ret = MergeSummary::default();
+
+        tx.execute(
+            "INSERT OR IGNORE INTO lives (id, started_at, ended_at) SELECT id, started_at, ended_at FROM other.lives;",
+            [],
+        )?;
+
+        //the values accumulated before the visits were recorded
+        tx.execute_batch(
+            "CREATE TEMP TABLE baselines AS SELECT
+                o.id,
+                o.name,
+                o.tag,
+                o.visit_count - (SELECT COUNT(DISTINCT live_id) FROM other.visits WHERE listener_id = o.id) AS visit_count,
+                o.stay_duration - (SELECT IFNULL(SUM(left_at - joined_at), 0) FROM other.visits WHERE listener_id = o.id AND left_at IS NOT NULL) AS stay_duration
+            FROM other.listeners o;
+            UPDATE main.listeners SET
+                visit_count = visit_count + MAX(0,
+                    (SELECT b.visit_count FROM baselines b WHERE b.id = listeners.id)
+                    - (visit_count - (SELECT COUNT(DISTINCT live_id) FROM main.visits WHERE listener_id = listeners.id))
+                ),
+                stay_duration = stay_duration + MAX(0,
+                    (SELECT b.stay_duration FROM baselines b WHERE b.id = listeners.id)
+                    - (stay_duration - (SELECT IFNULL(SUM(left_at - joined_at), 0) FROM main.visits WHERE listener_id = listeners.id AND left_at IS NOT NULL))
+                ),
+                legacy_visit_count = MAX(IFNULL(legacy_visit_count, 0), (SELECT b.visit_count FROM baselines b WHERE b.id = listeners.id)),
+                legacy_stay_duration = MAX(IFNULL(legacy_stay_duration, 0), (SELECT b.stay_duration FROM baselines b WHERE b.id = listeners.id))
+            WHERE id IN (SELECT id FROM baselines);",
+        )?;
+        ret.num_new_listener = tx.execute(
+            "INSERT INTO main.listeners (id, name, tag, legacy_visit_count, legacy_stay_duration, visit_count, stay_duration)
+            SELECT id, name, tag, visit_count, stay_duration, visit_count, stay_duration FROM baselines
+            WHERE id NOT IN (SELECT id FROM main.listeners);",
+            [],
+        )?;
+        tx.execute("DROP TABLE baselines;", [])?;
+
+        //The triggers add the visits to the cumulative values.
+        ret.num_visit = tx.execute(
+            "INSERT INTO main.visits (listener_id, live_id, joined_at, left_at)
+            SELECT listener_id, live_id, joined_at, left_at FROM other.visits o
+            WHERE NOT EXISTS (
+                SELECT 1 FROM main.visits v
+                WHERE v.listener_id = o.listener_id AND v.live_id = o.live_id AND v.joined_at = o.joined_at
+            )
+            ORDER BY o.id;",
+            [],
+        )?;
+        ret.num_comment = tx.execute(
+            "INSERT INTO main.comments (listener_id, live_id, message, created_at)
+            SELECT listener_id, live_id, message, created_at FROM other.comments o
+            WHERE NOT EXISTS (
+                SELECT 1 FROM main.comments c
+                WHERE c.listener_id = o.listener_id AND c.live_id = o.live_id AND c.created_at = o.created_at AND c.message = o.message
+            )
+            ORDER BY o.id;",
+            [],
+        )?;
+        ret.num_gift = tx.execute(
+            "INSERT INTO main.gifts (listener_id, live_id, kind, item, amount, combo, created_at)
+            SELECT listener_id, live_id, kind, item, amount, combo, created_at FROM other.gifts o
+            WHERE NOT EXISTS (
+                SELECT 1 FROM main.gifts g
+                WHERE g.listener_id = o.listener_id AND g.live_id = o.live_id AND g.created_at = o.created_at
+                    AND g.kind = o.kind AND g.item = o.item AND g.amount = o.amount AND g.combo = o.combo
+            )
+            ORDER BY o.id;",
+            [],
+        )?;
+        tx.execute(
+            "INSERT INTO main.names (listener_id, name, tag, seen_at)
+            SELECT listener_id, name, tag, seen_at FROM other.names o
+            WHERE NOT EXISTS (
+                SELECT 1 FROM main.names n
+                WHERE n.listener_id = o.listener_id AND n.name = o.name AND n.tag = o.tag AND n.seen_at IS o.seen_at
+            )
+            ORDER BY o.id;",
+            [],
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO main.milestones (listener_id, kind, value, achieved_at)
+            SELECT listener_id, kind, value, achieved_at FROM other.milestones;",
+            [],
+        )?;
+        tx.execute(
+            "INSERT INTO main.notes (listener_id, content, created_at)
+            SELECT listener_id, content, created_at FROM other.notes o
+            WHERE NOT EXISTS (
+                SELECT 1 FROM main.notes n
+                WHERE n.listener_id = o.listener_id AND n.content = o.content AND n.created_at = o.created_at
+            )
+            ORDER BY o.id;",
+            [],
+        )?;
+        //The tags set in this database take precedence.
+        tx.execute(
+            "INSERT OR IGNORE INTO main.listener_tags (listener_id, tag, value)
+            SELECT listener_id, tag, value FROM other.listener_tags;",
+            [],
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO main.listener_voices (listener_id, voice)
+            SELECT listener_id, voice FROM other.listener_voices;",
+            [],
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO main.pronunciations (pattern, reading, is_regex, created_at)
+            SELECT pattern, reading, is_regex, created_at FROM other.pronunciations;",
+            [],
+        )?;
+
+        tx.commit()?;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::super::{from_timestamp, ListenerEntity};
+    use super::*;
+
+    fn temp_db_path(name: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("spoon_comment_viewer_archive_{}.sqlite3", name));
+        let _ = fs::remove_file(&path);
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+        let db = Database::new(None).unwrap();
+        db.insert(ListenerEntity::new(
+            1,
+            "Mike".to_string(),
+            "mike".to_string(),
+            2,
+            Duration::from_secs(100),
+        ))
+        .unwrap();
+        db.insert_live(10, t(0)).unwrap();
+        db.start_visit(1, 10, t(0)).unwrap();
+        db.end_all_visits(10, t(50)).unwrap();
+
+        let path = temp_db_path("test01");
+        db.backup(&path).unwrap();
+        let copy = Database::new(Some(&path)).unwrap();
+        assert_eq!(db.select_all().unwrap(), copy.select_all().unwrap());
+        assert_eq!(
+            vec![vec![
+                json!(1),
+                json!(10),
+                json!(1_700_000_000),
+                json!(1_700_000_050)
+            ]],
+            copy.dump_table(
+                "visits",
+                &["listener_id", "live_id", "joined_at", "left_at"]
+            )
+            .unwrap()
+        );
+
+        //a co-host's database
+        copy.insert(ListenerEntity::new(
+            2,
+            "Lisa".to_string(),
+            "lisa".to_string(),
+            0,
+            Duration::ZERO,
+        ))
+        .unwrap();
+        copy.insert_live(11, t(100)).unwrap();
+        copy.start_visit(1, 11, t(100)).unwrap();
+        copy.start_visit(2, 11, t(100)).unwrap();
+        copy.end_all_visits(11, t(130)).unwrap();
+        drop(copy);
+
+        let summary = db.merge(&path).unwrap();
+        assert_eq!(
+            MergeSummary {
+                num_new_listener: 1,
+                num_visit: 2,
+                num_comment: 0,
+                num_gift: 0,
+            },
+            summary
+        );
+        let expected = vec![
+            ListenerEntity::new(
+                1,
+                "Mike".to_string(),
+                "mike".to_string(),
+                4,
+                Duration::from_secs(180),
+            ),
+            ListenerEntity::new(
+                2,
+                "Lisa".to_string(),
+                "lisa".to_string(),
+                1,
+                Duration::from_secs(30),
+            ),
+        ];
+        assert_eq!(expected, db.select_all().unwrap());
+        assert_eq!(2, db.select_lives().unwrap().len());
+
+        //idempotent
+        assert_eq!(MergeSummary::default(), db.merge(&path).unwrap());
+        assert_eq!(expected, db.select_all().unwrap());
+
+        assert!(db.merge(&temp_db_path("not_found")).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Local};
+use rusqlite::{params, Row, Statement};
+
+use super::{from_timestamp, Database, DatabaseError};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CommentEntity {
+    pub id: i64,
+    pub listener_id: usize,
+    pub live_id: u64,
+    pub message: String,
+    pub created_at: DateTime<Local>,
+}
+
+impl CommentEntity {
+    fn from_row(r: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: r.get(0)?,
+            listener_id: r.get(1)?,
+            live_id: r.get(2)?,
+            message: r.get(3)?,
+            created_at: from_timestamp(r.get(4)?),
+        })
+    }
+}
+
+impl Database {
+    pub fn insert_comment(
+        &self,
+        listener_id: usize,
+        live_id: u64,
+        message: &str,
+        created_at: DateTime<Local>,
+    ) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT INTO comments (listener_id, live_id, message, created_at) VALUES (?, ?, ?, ?);",
+            params![listener_id, live_id, message, created_at.timestamp()],
+        )?;
+        Ok(())
+    }
+
+    //the last `n` comments of the listener, the newest first
+    pub fn select_last_comments(
+        &self,
+        listener_id: usize,
+        n: usize,
+    ) -> Result<Vec<CommentEntity>, DatabaseError> {
+        let mut statement: Statement = self.conn.prepare(
+            "SELECT id, listener_id, live_id, message, created_at FROM comments
+                WHERE listener_id = ? ORDER BY created_at DESC, id DESC LIMIT ?;",
+        )?;
+        let ret = statement
+            .query_map(params![listener_id, n], CommentEntity::from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+
+        db.insert_comment(1, 10, "hello", t(0)).unwrap();
+        db.insert_comment(2, 10, "hi", t(1)).unwrap();
+        db.insert_comment(1, 10, "world", t(2)).unwrap();
+        db.insert_comment(1, 11, "again", t(2)).unwrap();
+
+        assert_eq!(
+            vec!["again", "world"],
+            db.select_last_comments(1, 2)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.message)
+                .collect::<Vec<_>>()
+        );
+        let comments = db.select_last_comments(2, 10).unwrap();
+        assert_eq!(1, comments.len());
+        assert_eq!((10, t(1)), (comments[0].live_id, comments[0].created_at));
+        assert!(db.select_last_comments(3, 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,59 @@
+use std::{error::Error, fmt};
+
+use rusqlite::ErrorCode;
+
+#[derive(Debug)]
+pub enum DatabaseError {
+    Sqlite(rusqlite::Error),
+    //the database was created by a newer version of this bot
+    UnsupportedVersion { version: usize, latest: usize },
+    //the linked SQLite library is too old to migrate the database
+    OldSqlite { version: String, required: String },
+    FileNotFound(String),
+}
+
+impl DatabaseError {
+    //whether the failure is likely to be temporary (e.g. the database is locked by the sqlite3 shell)
+    pub fn is_busy(&self) -> bool {
+        match self {
+            DatabaseError::Sqlite(rusqlite::Error::SqliteFailure(e, _)) => {
+                (e.code == ErrorCode::DatabaseBusy) || (e.code == ErrorCode::DatabaseLocked)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DatabaseError::Sqlite(e) => write!(f, "database error: {}", e),
+            DatabaseError::UnsupportedVersion { version, latest } => write!(
+                f,
+                "the database is of the version {}, which is newer than the supported one ({})",
+                version, latest
+            ),
+            DatabaseError::OldSqlite { version, required } => write!(
+                f,
+                "SQLite {} is too old to migrate the database; {} or later is required",
+                version, required
+            ),
+            DatabaseError::FileNotFound(path) => write!(f, "database [ {} ] is not found", path),
+        }
+    }
+}
+
+impl Error for DatabaseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DatabaseError::Sqlite(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for DatabaseError {
+    fn from(e: rusqlite::Error) -> Self {
+        DatabaseError::Sqlite(e)
+    }
+}
@@ -0,0 +1,219 @@
+use chrono::{DateTime, Local};
+use rusqlite::{params, Row, Statement};
+
+use super::{from_timestamp, Database, DatabaseError};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GiftKind {
+    Heart,
+    Spoon,
+    Buster,
+    Item, //`use_item` such as 粗品
+}
+
+impl GiftKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GiftKind::Heart => "heart",
+            GiftKind::Spoon => "spoon",
+            GiftKind::Buster => "buster",
+            GiftKind::Item => "item",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "heart" => Some(GiftKind::Heart),
+            "spoon" => Some(GiftKind::Spoon),
+            "buster" => Some(GiftKind::Buster),
+            "item" => Some(GiftKind::Item),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GiftEntity {
+    pub id: i64,
+    pub listener_id: usize,
+    pub live_id: u64,
+    pub kind: GiftKind,
+    pub item: String, //the item id or the effect of `use_item`, or empty
+    pub amount: i64,
+    pub combo: i64,
+    pub created_at: DateTime<Local>,
+}
+
+impl GiftEntity {
+    pub fn new(
+        listener_id: usize,
+        live_id: u64,
+        kind: GiftKind,
+        item: &str,
+        amount: i64,
+        combo: i64,
+        created_at: DateTime<Local>,
+    ) -> Self {
+        Self {
+            id: 0, //assigned on insertion
+            listener_id,
+            live_id,
+            kind,
+            item: item.to_string(),
+            amount,
+            combo,
+            created_at,
+        }
+    }
+
+    fn from_row(r: &Row) -> rusqlite::Result<Self> {
+        let kind: String = r.get(3)?;
+        Ok(Self {
+            id: r.get(0)?,
+            listener_id: r.get(1)?,
+            live_id: r.get(2)?,
+            kind: GiftKind::from_str(&kind).ok_or_else(|| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    3,
+                    rusqlite::types::Type::Text,
+                    format!("unknown gift kind `{}`", kind).into(),
+                )
+            })?,
+            item: r.get(4)?,
+            amount: r.get(5)?,
+            combo: r.get(6)?,
+            created_at: from_timestamp(r.get(7)?),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GifterEntity {
+    pub listener_id: usize,
+    pub name: String, //empty if the listener is not in `listeners`
+    pub num_spoon: i64,
+}
+
+impl Database {
+    pub fn insert_gift(&self, entity: &GiftEntity) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                "INSERT INTO gifts (listener_id, live_id, kind, item, amount, combo, created_at) VALUES (?, ?, ?, ?, ?, ?, ?);",
+                params![
+                    entity.listener_id,
+                    entity.live_id,
+                    entity.kind.as_str(),
+                    entity.item,
+                    entity.amount,
+                    entity.combo,
+                    entity.created_at.timestamp(),
+                ],
+            )?;
+        Ok(())
+    }
+
+    //in chronological order
+    pub fn select_gifts_by_live(&self, live_id: u64) -> Result<Vec<GiftEntity>, DatabaseError> {
+        let mut statement: Statement = self.conn.prepare(
+            "SELECT id, listener_id, live_id, kind, item, amount, combo, created_at FROM gifts
+                WHERE live_id = ? ORDER BY created_at, id;",
+        )?;
+        let ret = statement
+            .query_map([live_id], GiftEntity::from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+
+    //the number of spoons the listener has ever sent
+    pub fn total_spoons(&self, listener_id: usize) -> Result<i64, DatabaseError> {
+        Ok(self.conn.query_row(
+            "SELECT IFNULL(SUM(amount * combo), 0) FROM gifts WHERE listener_id = ? AND kind = ?;",
+            params![listener_id, GiftKind::Spoon.as_str()],
+            |r| r.get(0),
+        )?)
+    }
+
+    //the top `n` listeners by the number of spoons sent in the live
+    pub fn top_gifters(&self, live_id: u64, n: usize) -> Result<Vec<GifterEntity>, DatabaseError> {
+        let mut statement: Statement = self.conn.prepare(
+            "SELECT g.listener_id, IFNULL(l.name, ''), SUM(g.amount * g.combo) AS num_spoon
+                FROM gifts g LEFT JOIN listeners l ON l.id = g.listener_id
+                WHERE g.live_id = ? AND g.kind = ?
+                GROUP BY g.listener_id
+                ORDER BY num_spoon DESC, g.listener_id
+                LIMIT ?;",
+        )?;
+        let ret = statement
+            .query_map(params![live_id, GiftKind::Spoon.as_str(), n], |r| {
+                Ok(GifterEntity {
+                    listener_id: r.get(0)?,
+                    name: r.get(1)?,
+                    num_spoon: r.get(2)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::ListenerEntity;
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+
+        db.insert(ListenerEntity::new(
+            1,
+            "Mike".to_string(),
+            "mike".to_string(),
+            1,
+            Duration::ZERO,
+        ))
+        .unwrap();
+
+        db.insert_gift(&GiftEntity::new(1, 10, GiftKind::Spoon, "", 10, 3, t(0)))
+            .unwrap();
+        db.insert_gift(&GiftEntity::new(1, 10, GiftKind::Heart, "", 1, 1, t(1)))
+            .unwrap();
+        db.insert_gift(&GiftEntity::new(2, 10, GiftKind::Spoon, "", 50, 1, t(2)))
+            .unwrap();
+        db.insert_gift(&GiftEntity::new(3, 10, GiftKind::Spoon, "", 1, 1, t(3)))
+            .unwrap();
+        db.insert_gift(&GiftEntity::new(1, 11, GiftKind::Spoon, "", 100, 1, t(4)))
+            .unwrap();
+        db.insert_gift(&GiftEntity::new(3, 11, GiftKind::Item, "34", 1, 1, t(5)))
+            .unwrap();
+
+        assert_eq!(130, db.total_spoons(1).unwrap());
+        assert_eq!(0, db.total_spoons(4).unwrap());
+        assert_eq!(
+            vec![
+                GifterEntity {
+                    listener_id: 2,
+                    name: "".to_string(),
+                    num_spoon: 50,
+                },
+                GifterEntity {
+                    listener_id: 1,
+                    name: "Mike".to_string(),
+                    num_spoon: 30,
+                },
+            ],
+            db.top_gifters(10, 2).unwrap()
+        );
+
+        let gifts = db.select_gifts_by_live(11).unwrap();
+        assert_eq!(2, gifts.len());
+        assert_eq!(
+            (GiftKind::Item, "34"),
+            (gifts[1].kind, gifts[1].item.as_str())
+        );
+    }
+}
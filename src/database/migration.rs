@@ -0,0 +1,262 @@
+use log::info;
+use rusqlite::Connection;
+
+use super::DatabaseError;
+
+//The `i`-th step migrates the schema from the version `i` to `i + 1`, and the version is stored in `PRAGMA user_version`.
+//Never modify a released step. Append a new one instead.
+const MIGRATIONS: &[&str] = &[
+    //v1
+    //`IF NOT EXISTS` as the databases created before the migration was introduced already have this table at the version `0`.
+    "CREATE TABLE IF NOT EXISTS listeners (
+        id                INTEGER PRIMARY KEY,
+        name              TEXT,
+        visit_count       INTEGER,
+        stay_duration     INTEGER
+    );",
+    //v2
+    "ALTER TABLE listeners ADD COLUMN tag TEXT NOT NULL DEFAULT '';",
+    //v3
+    //The cumulative values are now derived from `visits`, and the ones accumulated before this version are kept as the baselines.
+    "ALTER TABLE listeners RENAME COLUMN visit_count TO legacy_visit_count;
+    ALTER TABLE listeners RENAME COLUMN stay_duration TO legacy_stay_duration;
+    CREATE TABLE lives (
+        id                INTEGER PRIMARY KEY,
+        started_at        INTEGER NOT NULL,
+        ended_at          INTEGER
+    );
+    CREATE TABLE visits (
+        id                INTEGER PRIMARY KEY AUTOINCREMENT,
+        listener_id       INTEGER NOT NULL,
+        live_id           INTEGER NOT NULL,
+        joined_at         INTEGER NOT NULL,
+        left_at           INTEGER
+    );
+    CREATE INDEX visits_listener_id ON visits (listener_id);
+    CREATE INDEX visits_live_id ON visits (live_id);",
+    //v4
+    "CREATE TABLE comments (
+        id                INTEGER PRIMARY KEY AUTOINCREMENT,
+        listener_id       INTEGER NOT NULL,
+        live_id           INTEGER NOT NULL,
+        message           TEXT NOT NULL,
+        created_at        INTEGER NOT NULL
+    );
+    CREATE TABLE gifts (
+        id                INTEGER PRIMARY KEY AUTOINCREMENT,
+        listener_id       INTEGER NOT NULL,
+        live_id           INTEGER NOT NULL,
+        kind              TEXT NOT NULL,
+        item              TEXT NOT NULL,
+        amount            INTEGER NOT NULL,
+        combo             INTEGER NOT NULL,
+        created_at        INTEGER NOT NULL
+    );
+    CREATE INDEX comments_listener_id ON comments (listener_id);
+    CREATE INDEX gifts_listener_id ON gifts (listener_id);
+    CREATE INDEX gifts_live_id ON gifts (live_id);",
+    //v5
+    //The current names are taken over with the unknown timestamps.
+    "CREATE TABLE names (
+        id                INTEGER PRIMARY KEY AUTOINCREMENT,
+        listener_id       INTEGER NOT NULL,
+        name              TEXT NOT NULL,
+        tag               TEXT NOT NULL,
+        seen_at           INTEGER
+    );
+    CREATE INDEX names_listener_id ON names (listener_id);
+    INSERT INTO names (listener_id, name, tag) SELECT id, IFNULL(name, ''), tag FROM listeners;",
+    //v6
+    //The cumulative values are materialized and kept in sync with `visits` by the triggers so that the ranking can be answered by the index.
+    //The `legacy_*` columns still hold the values accumulated before the visits were recorded.
+    "ALTER TABLE listeners ADD COLUMN visit_count INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE listeners ADD COLUMN stay_duration INTEGER NOT NULL DEFAULT 0;
+    UPDATE listeners SET
+        visit_count = IFNULL(legacy_visit_count, 0) + (SELECT COUNT(DISTINCT live_id) FROM visits WHERE listener_id = listeners.id),
+        stay_duration = IFNULL(legacy_stay_duration, 0) + (SELECT IFNULL(SUM(left_at - joined_at), 0) FROM visits WHERE listener_id = listeners.id AND left_at IS NOT NULL);
+    CREATE INDEX listeners_stay_duration ON listeners (stay_duration DESC, visit_count DESC, id);
+    CREATE INDEX listeners_visit_count ON listeners (visit_count DESC, stay_duration DESC, id);
+    CREATE INDEX visits_listener_id_live_id ON visits (listener_id, live_id);
+    CREATE TRIGGER visits_count_visit AFTER INSERT ON visits
+    WHEN NOT EXISTS (SELECT 1 FROM visits WHERE listener_id = NEW.listener_id AND live_id = NEW.live_id AND id <> NEW.id)
+    BEGIN
+        UPDATE listeners SET visit_count = visit_count + 1 WHERE id = NEW.listener_id;
+    END;
+    CREATE TRIGGER visits_add_finished_stay AFTER INSERT ON visits
+    WHEN NEW.left_at IS NOT NULL
+    BEGIN
+        UPDATE listeners SET stay_duration = stay_duration + NEW.left_at - NEW.joined_at WHERE id = NEW.listener_id;
+    END;
+    CREATE TRIGGER visits_add_stay AFTER UPDATE OF left_at ON visits
+    WHEN OLD.left_at IS NULL AND NEW.left_at IS NOT NULL
+    BEGIN
+        UPDATE listeners SET stay_duration = stay_duration + NEW.left_at - NEW.joined_at WHERE id = NEW.listener_id;
+    END;",
+    //v7
+    "CREATE TABLE milestones (
+        listener_id       INTEGER NOT NULL,
+        kind              TEXT NOT NULL,
+        value             INTEGER NOT NULL,
+        achieved_at       INTEGER NOT NULL,
+        PRIMARY KEY (listener_id, kind, value)
+    );",
+    //v8
+    "CREATE TABLE notes (
+        id                INTEGER PRIMARY KEY AUTOINCREMENT,
+        listener_id       INTEGER NOT NULL,
+        content           TEXT NOT NULL,
+        created_at        INTEGER NOT NULL
+    );
+    CREATE INDEX notes_listener_id ON notes (listener_id);
+    CREATE TABLE listener_tags (
+        listener_id       INTEGER NOT NULL,
+        tag               TEXT NOT NULL,
+        value             TEXT NOT NULL,
+        PRIMARY KEY (listener_id, tag)
+    );",
+    //v9
+    "CREATE TABLE pronunciations (
+        pattern           TEXT PRIMARY KEY,
+        reading           TEXT NOT NULL,
+        is_regex          INTEGER NOT NULL,
+        created_at        INTEGER NOT NULL
+    );",
+    //v10
+    "CREATE TABLE listener_voices (
+        listener_id       INTEGER PRIMARY KEY,
+        voice             TEXT NOT NULL
+    );",
+];
+
+//`ALTER TABLE .. RENAME COLUMN` in v3 is supported since SQLite 3.25.0.
+const MIN_SQLITE_VERSION_NUMBER: i32 = 3_025_000;
+
+//`version_number` is in the format of `rusqlite::version_number()`, e.g. `3025000` for 3.25.0
+fn check_sqlite_version(version_number: i32) -> Result<(), DatabaseError> {
+    if (version_number >= MIN_SQLITE_VERSION_NUMBER) {
+        return Ok(());
+    }
+    let format = |n: i32| format!("{}.{}.{}", n / 1_000_000, n / 1_000 % 1_000, n % 1_000);
+    Err(DatabaseError::OldSqlite {
+        version: format(version_number),
+        required: format(MIN_SQLITE_VERSION_NUMBER),
+    })
+}
+
+pub fn latest_version() -> usize {
+    MIGRATIONS.len()
+}
+
+pub fn version(conn: &Connection) -> Result<usize, DatabaseError> {
+    Ok(conn.query_row("PRAGMA user_version;", [], |r| r.get(0))?)
+}
+
+//applies the pending migration steps, each in its own transaction
+pub fn migrate(conn: &mut Connection) -> Result<(), DatabaseError> {
+    let current = version(conn)?;
+    if (current > latest_version()) {
+        return Err(DatabaseError::UnsupportedVersion {
+            version: current,
+            latest: latest_version(),
+        });
+    }
+    if (current < latest_version()) {
+        check_sqlite_version(rusqlite::version_number())?;
+    }
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+        info!("Migrated the database to the version {}.", i + 1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::Duration};
+
+    use super::super::{Database, ListenerEntity};
+    use super::*;
+
+    fn temp_db_path(name: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("spoon_comment_viewer_migration_{}.sqlite3", name));
+        let _ = fs::remove_file(&path);
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        //a database file of the version 1
+        let path = temp_db_path("test01");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(MIGRATIONS[0]).unwrap();
+            conn.execute(
+                "INSERT INTO listeners (id, name, visit_count, stay_duration) VALUES (1, 'Mike', 2, 3);",
+                [],
+            )
+            .unwrap();
+            conn.pragma_update(None, "user_version", 1).unwrap();
+        }
+
+        let db = Database::new(Some(&path)).unwrap();
+        assert_eq!(latest_version(), version(&db.conn).unwrap());
+        assert_eq!(
+            Some(ListenerEntity::new(
+                1,
+                "Mike".to_string(),
+                "".to_string(),
+                2,
+                Duration::from_secs(3)
+            )),
+            db.select_by_id(1).unwrap()
+        );
+        drop(db);
+
+        //idempotent
+        let db = Database::new(Some(&path)).unwrap();
+        assert_eq!(latest_version(), version(&db.conn).unwrap());
+        assert_eq!(1, db.select_all().unwrap().len());
+        drop(db);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        //a database file created before the migration was introduced
+        let path = temp_db_path("test02");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(MIGRATIONS[0]).unwrap();
+        }
+        let mut conn = Connection::open(&path).unwrap();
+        assert_eq!(0, version(&conn).unwrap());
+        migrate(&mut conn).unwrap();
+        assert_eq!(latest_version(), version(&conn).unwrap());
+
+        //a database file newer than this binary
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+        assert!(migrate(&mut conn).is_err());
+        drop(conn);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        assert!(check_sqlite_version(rusqlite::version_number()).is_ok());
+        assert!(check_sqlite_version(3_025_000).is_ok());
+        assert_eq!(
+            "SQLite 3.24.1 is too old to migrate the database; 3.25.0 or later is required",
+            check_sqlite_version(3_024_001).unwrap_err().to_string()
+        );
+    }
+}
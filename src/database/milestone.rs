@@ -0,0 +1,114 @@
+use chrono::{DateTime, Local};
+use rusqlite::{params, Statement};
+
+use super::{Database, DatabaseError};
+
+//Each milestone is celebrated only once per listener.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Milestone {
+    VisitCount(usize),
+    StayHours(u64),
+    FirstSpoon,
+}
+
+impl Milestone {
+    fn kind(&self) -> &'static str {
+        match self {
+            Milestone::VisitCount(_) => "visit_count",
+            Milestone::StayHours(_) => "stay_hours",
+            Milestone::FirstSpoon => "first_spoon",
+        }
+    }
+
+    fn value(&self) -> i64 {
+        match self {
+            Milestone::VisitCount(n) => *n as i64,
+            Milestone::StayHours(n) => *n as i64,
+            Milestone::FirstSpoon => 0,
+        }
+    }
+
+    fn from_kind_and_value(kind: &str, value: i64) -> Option<Self> {
+        match kind {
+            "visit_count" => Some(Milestone::VisitCount(value as usize)),
+            "stay_hours" => Some(Milestone::StayHours(value as u64)),
+            "first_spoon" => Some(Milestone::FirstSpoon),
+            _ => None,
+        }
+    }
+
+    //whether `other` is a larger milestone of the same kind
+    pub fn is_exceeded_by(&self, other: &Milestone) -> bool {
+        (self.kind() == other.kind()) && (self.value() < other.value())
+    }
+}
+
+impl Database {
+    //returns `false` if the listener has already achieved the milestone
+    pub fn insert_milestone(
+        &self,
+        listener_id: usize,
+        milestone: Milestone,
+        achieved_at: DateTime<Local>,
+    ) -> Result<bool, DatabaseError> {
+        let n = self.conn.execute(
+            "INSERT OR IGNORE INTO milestones (listener_id, kind, value, achieved_at) VALUES (?, ?, ?, ?);",
+            params![
+                listener_id,
+                milestone.kind(),
+                milestone.value(),
+                achieved_at.timestamp(),
+            ],
+        )?;
+        Ok(n == 1)
+    }
+
+    //in the order of achievement
+    pub fn select_milestones(&self, listener_id: usize) -> Result<Vec<Milestone>, DatabaseError> {
+        let mut statement: Statement = self.conn.prepare(
+            "SELECT kind, value FROM milestones WHERE listener_id = ? ORDER BY achieved_at, rowid;",
+        )?;
+        let ret = statement
+            .query_map([listener_id], |r| {
+                Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ret
+            .into_iter()
+            .filter_map(|(kind, value)| Milestone::from_kind_and_value(&kind, value))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::from_timestamp;
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+
+        assert!(db
+            .insert_milestone(1, Milestone::VisitCount(10), t(0))
+            .unwrap());
+        assert!(db.insert_milestone(1, Milestone::FirstSpoon, t(1)).unwrap());
+        assert!(!db
+            .insert_milestone(1, Milestone::VisitCount(10), t(2))
+            .unwrap());
+        assert!(db
+            .insert_milestone(2, Milestone::VisitCount(10), t(3))
+            .unwrap());
+        assert_eq!(
+            vec![Milestone::VisitCount(10), Milestone::FirstSpoon],
+            db.select_milestones(1).unwrap()
+        );
+        assert!(db.select_milestones(3).unwrap().is_empty());
+
+        assert!(Milestone::VisitCount(10).is_exceeded_by(&Milestone::VisitCount(50)));
+        assert!(!Milestone::VisitCount(50).is_exceeded_by(&Milestone::VisitCount(10)));
+        assert!(!Milestone::VisitCount(10).is_exceeded_by(&Milestone::StayHours(50)));
+    }
+}
@@ -0,0 +1,241 @@
+mod archive;
+mod comment;
+mod error;
+mod gift;
+pub mod migration;
+mod milestone;
+mod name;
+mod note;
+mod pending;
+mod pronunciation;
+mod ranking;
+mod stats;
+mod visit;
+mod voice;
+
+use std::time::Duration;
+
+use chrono::{DateTime, Local, TimeZone};
+use rusqlite::{params, Connection, Statement};
+
+pub use archive::MergeSummary;
+pub use comment::CommentEntity;
+pub use error::DatabaseError;
+pub use gift::{GiftEntity, GiftKind, GifterEntity};
+pub use milestone::Milestone;
+pub use name::NameEntity;
+pub use note::{NoteEntity, TagEntity};
+pub use pending::{PendingWrite, WriteBuffer};
+pub use pronunciation::PronunciationEntity;
+pub use stats::{GiftTotal, LiveSummary};
+pub use visit::{LiveEntity, VisitEntity};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ListenerEntity {
+    pub id: usize,
+    pub name: String,
+    pub tag: String, //user id such as `@momo_chan` but WITHOUT `@`
+    pub visit_count: usize,
+    pub stay_duration: Duration,
+}
+
+impl ListenerEntity {
+    pub fn new(
+        id: usize,
+        name: String,
+        tag: String,
+        visit_count: usize,
+        stay_duration: Duration,
+    ) -> ListenerEntity {
+        ListenerEntity {
+            id,
+            name,
+            tag,
+            visit_count,
+            stay_duration,
+        }
+    }
+}
+
+const TABLE_NAME: &str = "listeners";
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(3);
+//used while a live is on air, where waiting for the lock would stall the bot (see `WriteBuffer::flush_if_due()`)
+pub const LIVE_BUSY_TIMEOUT: Duration = Duration::from_millis(50);
+
+//Timestamps are stored as UNIX time in seconds.
+fn from_timestamp(t: i64) -> DateTime<Local> {
+    Local.timestamp_opt(t, 0).unwrap()
+}
+
+pub struct Database {
+    conn: Connection,
+    table_name: &'static str,
+}
+
+impl Database {
+    pub fn new(db_path: Option<&str>) -> Result<Self, DatabaseError> {
+        let mut conn = if let Some(s) = db_path {
+            Connection::open(s)?
+        } else {
+            Connection::open_in_memory()?
+        };
+        //waits for a while instead of failing immediately when e.g. the sqlite3 shell is writing to the database
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        migration::migrate(&mut conn)?;
+
+        Ok(Self {
+            conn,
+            table_name: TABLE_NAME,
+        })
+    }
+
+    //how long a query waits for the database to be unlocked (`BUSY_TIMEOUT` by default)
+    pub fn set_busy_timeout(&self, timeout: Duration) -> Result<(), DatabaseError> {
+        self.conn.busy_timeout(timeout)?;
+        Ok(())
+    }
+
+    //in the order of `ListenerEntity::new()`
+    //`visit_count` is the number of the lives visited and `stay_duration` is the sum of the finished visits,
+    // each added to the value accumulated before the visits were recorded (see the migrations v3 and v6).
+    fn select_statement(&self) -> String {
+        format!(
+            "SELECT id, name, tag, visit_count, stay_duration FROM {}",
+            self.table_name
+        )
+    }
+
+    fn to_entity(r: &rusqlite::Row) -> rusqlite::Result<ListenerEntity> {
+        Ok(ListenerEntity::new(
+            r.get(0)?,
+            r.get(1)?,
+            r.get(2)?,
+            r.get(3)?,
+            Duration::from_secs(r.get(4)?),
+        ))
+    }
+
+    //The cumulative values of `entity` are used as is; the visits recorded later are added to them.
+    pub fn insert(&self, entity: ListenerEntity) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            &format!(
+                "INSERT INTO {} (id, name, tag, legacy_visit_count, legacy_stay_duration, visit_count, stay_duration) VALUES (?1, ?2, ?3, ?4, ?5, ?4, ?5);",
+                self.table_name
+            ),
+            params![
+                entity.id,
+                entity.name,
+                entity.tag,
+                entity.visit_count,
+                entity.stay_duration.as_secs(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    //overwrites the cumulative values; the visits recorded later are added to them
+    pub fn update(&self, entity: ListenerEntity) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            &format!(
+                "UPDATE {} SET name = ?, tag = ?, visit_count = ?, stay_duration = ? WHERE id = ?;",
+                self.table_name
+            ),
+            params![
+                entity.name,
+                entity.tag,
+                entity.visit_count,
+                entity.stay_duration.as_secs(),
+                entity.id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    //also records the name in the history if it has changed (see `select_names()`)
+    pub fn update_name(
+        &self,
+        id: usize,
+        name: &str,
+        tag: &str,
+        seen_at: DateTime<Local>,
+    ) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            &format!(
+                "UPDATE {} SET name = ?, tag = ? WHERE id = ?;",
+                self.table_name
+            ),
+            params![name, tag, id],
+        )?;
+        self.record_name(id, name, tag, seen_at)
+    }
+
+    pub fn select_by_id(&self, id: usize) -> Result<Option<ListenerEntity>, DatabaseError> {
+        let mut statement: Statement = self
+            .conn
+            .prepare(&format!("{} WHERE id = ?;", self.select_statement()))?;
+        let listeners = statement
+            .query_map([id], Self::to_entity)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(listeners.first().cloned())
+    }
+
+    pub fn select_all(&self) -> Result<Vec<ListenerEntity>, DatabaseError> {
+        let mut statement: Statement = self
+            .conn
+            .prepare(&format!("{};", self.select_statement()))?;
+        let ret = statement
+            .query_map([], Self::to_entity)?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+
+        let mut entity1 = ListenerEntity::new(
+            1,
+            "Mike".to_string(),
+            "mike".to_string(),
+            2,
+            Duration::from_secs(3),
+        );
+        let entity2 = ListenerEntity::new(
+            10,
+            "Lisa".to_string(),
+            "lisa".to_string(),
+            20,
+            Duration::from_secs(30),
+        );
+        db.insert(entity1.clone()).unwrap();
+        db.insert(entity2.clone()).unwrap();
+
+        assert_eq!(
+            vec![entity1.clone(), entity2.clone()],
+            db.select_all().unwrap()
+        );
+        assert_eq!(None, db.select_by_id(0).unwrap());
+        assert_eq!(Some(entity1.clone()), db.select_by_id(1).unwrap());
+        assert_eq!(Some(entity2.clone()), db.select_by_id(10).unwrap());
+        println!("{:?}", db.select_all().unwrap());
+
+        entity1.name = "Bob".to_string();
+        entity1.tag = "bob".to_string();
+        entity1.visit_count *= 100;
+        entity1.stay_duration *= 100;
+        db.update(entity1.clone()).unwrap();
+        assert_eq!(
+            vec![entity1.clone(), entity2.clone()],
+            db.select_all().unwrap()
+        );
+        assert_eq!(Some(entity1), db.select_by_id(1).unwrap());
+        assert_eq!(Some(entity2), db.select_by_id(10).unwrap());
+        println!("{:?}", db.select_all().unwrap());
+    }
+}
@@ -0,0 +1,128 @@
+use chrono::{DateTime, Local};
+use rusqlite::{params, Row, Statement};
+
+use super::{from_timestamp, Database, DatabaseError};
+
+//a nickname (and a tag) a listener has used
+//Trolls frequently change their names, but the listener id never changes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct NameEntity {
+    pub listener_id: usize,
+    pub name: String,
+    pub tag: String,
+    pub seen_at: Option<DateTime<Local>>, //when the name was first seen (`None` if before the history was recorded)
+}
+
+impl NameEntity {
+    fn from_row(r: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            listener_id: r.get(0)?,
+            name: r.get(1)?,
+            tag: r.get(2)?,
+            seen_at: r.get::<_, Option<i64>>(3)?.map(from_timestamp),
+        })
+    }
+}
+
+impl Database {
+    //does nothing if the name and the tag are the same as the latest ones
+    pub(super) fn record_name(
+        &self,
+        listener_id: usize,
+        name: &str,
+        tag: &str,
+        seen_at: DateTime<Local>,
+    ) -> Result<(), DatabaseError> {
+        if let Some(latest) = self.select_names(listener_id)?.last() {
+            if ((latest.name == name) && (latest.tag == tag)) {
+                return Ok(());
+            }
+        }
+        self.conn.execute(
+            "INSERT INTO names (listener_id, name, tag, seen_at) VALUES (?, ?, ?, ?);",
+            params![listener_id, name, tag, seen_at.timestamp()],
+        )?;
+        Ok(())
+    }
+
+    //all the names the listener has used, the oldest first
+    pub fn select_names(&self, listener_id: usize) -> Result<Vec<NameEntity>, DatabaseError> {
+        let mut statement: Statement = self.conn.prepare(
+            "SELECT listener_id, name, tag, seen_at FROM names WHERE listener_id = ? ORDER BY id;",
+        )?;
+        let ret = statement
+            .query_map([listener_id], NameEntity::from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+
+    //the listeners who have ever used `name` as a nickname or a tag
+    pub fn select_listener_ids_by_name(&self, name: &str) -> Result<Vec<usize>, DatabaseError> {
+        let mut statement: Statement = self
+            .conn
+            .prepare(
+                "SELECT DISTINCT listener_id FROM names WHERE name = ?1 OR tag = ?1 ORDER BY listener_id;",
+            )?;
+        let ret = statement
+            .query_map([name], |r| r.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::ListenerEntity;
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+
+        db.insert(ListenerEntity::new(
+            1,
+            "Mike".to_string(),
+            "mike".to_string(),
+            0,
+            Duration::ZERO,
+        ))
+        .unwrap();
+        db.update_name(1, "Mike", "mike", t(0)).unwrap();
+        db.update_name(1, "Mike", "mike", t(1)).unwrap();
+        db.update_name(1, "Bob", "mike", t(2)).unwrap();
+        db.update_name(1, "Bob", "bob", t(3)).unwrap();
+        db.update_name(1, "Mike", "bob", t(4)).unwrap();
+
+        assert_eq!(
+            vec![
+                ("Mike", "mike", t(0)),
+                ("Bob", "mike", t(2)),
+                ("Bob", "bob", t(3)),
+                ("Mike", "bob", t(4)),
+            ],
+            db.select_names(1)
+                .unwrap()
+                .iter()
+                .map(|e| (e.name.as_str(), e.tag.as_str(), e.seen_at.unwrap()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("Mike", db.select_by_id(1).unwrap().unwrap().name);
+
+        db.insert(ListenerEntity::new(
+            2,
+            "Lisa".to_string(),
+            "lisa".to_string(),
+            0,
+            Duration::ZERO,
+        ))
+        .unwrap();
+        db.update_name(2, "Bob", "lisa", t(5)).unwrap();
+        assert_eq!(vec![1, 2], db.select_listener_ids_by_name("Bob").unwrap());
+        assert_eq!(vec![2], db.select_listener_ids_by_name("lisa").unwrap());
+        assert!(db.select_listener_ids_by_name("Alice").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, Local};
+use rusqlite::{params, Row, Statement};
+
+use super::{from_timestamp, Database, DatabaseError};
+
+//a free-form memo a streamer has written about a listener (e.g. `birthday 3/14`)
+#[derive(Debug, PartialEq, Clone)]
+pub struct NoteEntity {
+    pub id: i64,
+    pub listener_id: usize,
+    pub content: String,
+    pub created_at: DateTime<Local>,
+}
+
+impl NoteEntity {
+    fn from_row(r: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: r.get(0)?,
+            listener_id: r.get(1)?,
+            content: r.get(2)?,
+            created_at: from_timestamp(r.get(3)?),
+        })
+    }
+}
+
+//a label attached to a listener, some of which change how the bot treats the listener (see `notes.rs`)
+//`value` is empty for the tags which need no value.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TagEntity {
+    pub tag: String,
+    pub value: String,
+}
+
+impl Database {
+    //returns the id of the note
+    pub fn insert_note(
+        &self,
+        listener_id: usize,
+        content: &str,
+        created_at: DateTime<Local>,
+    ) -> Result<i64, DatabaseError> {
+        self.conn.execute(
+            "INSERT INTO notes (listener_id, content, created_at) VALUES (?, ?, ?);",
+            params![listener_id, content, created_at.timestamp()],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    //returns `false` if there is no such note
+    pub fn delete_note(&self, id: i64) -> Result<bool, DatabaseError> {
+        let n = self.conn.execute("DELETE FROM notes WHERE id = ?;", [id])?;
+        Ok(n == 1)
+    }
+
+    //the oldest first
+    pub fn select_notes(&self, listener_id: usize) -> Result<Vec<NoteEntity>, DatabaseError> {
+        let mut statement: Statement = self.conn.prepare(
+            "SELECT id, listener_id, content, created_at FROM notes WHERE listener_id = ? ORDER BY id;",
+        )?;
+        let ret = statement
+            .query_map([listener_id], NoteEntity::from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+
+    //overwrites the value if the listener already has the tag
+    pub fn set_tag(&self, listener_id: usize, tag: &str, value: &str) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO listener_tags (listener_id, tag, value) VALUES (?, ?, ?);",
+            params![listener_id, tag, value],
+        )?;
+        Ok(())
+    }
+
+    //returns `false` if the listener does not have the tag
+    pub fn remove_tag(&self, listener_id: usize, tag: &str) -> Result<bool, DatabaseError> {
+        let n = self.conn.execute(
+            "DELETE FROM listener_tags WHERE listener_id = ? AND tag = ?;",
+            params![listener_id, tag],
+        )?;
+        Ok(n == 1)
+    }
+
+    //in the alphabetical order of the tags
+    pub fn select_tags(&self, listener_id: usize) -> Result<Vec<TagEntity>, DatabaseError> {
+        let mut statement: Statement = self
+            .conn
+            .prepare("SELECT tag, value FROM listener_tags WHERE listener_id = ? ORDER BY tag;")?;
+        let ret = statement
+            .query_map([listener_id], |r| {
+                Ok(TagEntity {
+                    tag: r.get(0)?,
+                    value: r.get(1)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+
+        let id1 = db.insert_note(1, "birthday 3/14", t(0)).unwrap();
+        let id2 = db.insert_note(1, "likes cats", t(1)).unwrap();
+        db.insert_note(2, "troll", t(2)).unwrap();
+        assert_eq!(
+            vec!["birthday 3/14", "likes cats"],
+            db.select_notes(1)
+                .unwrap()
+                .into_iter()
+                .map(|note| note.content)
+                .collect::<Vec<_>>()
+        );
+        assert!(db.delete_note(id1).unwrap());
+        assert!(!db.delete_note(id1).unwrap());
+        assert_eq!(
+            vec![NoteEntity {
+                id: id2,
+                listener_id: 1,
+                content: "likes cats".to_string(),
+                created_at: t(1),
+            }],
+            db.select_notes(1).unwrap()
+        );
+
+        db.set_tag(1, "greeting", "hello").unwrap();
+        db.set_tag(1, "greeting", "hi").unwrap();
+        db.set_tag(1, "block", "").unwrap();
+        assert_eq!(
+            vec![
+                TagEntity {
+                    tag: "block".to_string(),
+                    value: "".to_string(),
+                },
+                TagEntity {
+                    tag: "greeting".to_string(),
+                    value: "hi".to_string(),
+                },
+            ],
+            db.select_tags(1).unwrap()
+        );
+        assert!(db.remove_tag(1, "block").unwrap());
+        assert!(!db.remove_tag(1, "block").unwrap());
+        assert!(db.select_tags(2).unwrap().is_empty());
+    }
+}
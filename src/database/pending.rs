@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use log::error;
+
+use super::{Database, DatabaseError, ListenerEntity};
+
+//a write made while a live is on air, which is retried later if the database is temporarily unavailable
+#[derive(Debug, PartialEq, Clone)]
+pub enum PendingWrite {
+    InsertListener(ListenerEntity), //ignored if already exists
+    UpdateName {
+        id: usize,
+        name: String,
+        tag: String,
+        seen_at: DateTime<Local>,
+    },
+    StartVisit {
+        listener_id: usize,
+        live_id: u64,
+        joined_at: DateTime<Local>,
+    },
+    EndVisit {
+        listener_id: usize,
+        live_id: u64,
+        left_at: DateTime<Local>,
+    },
+    EndAllVisits {
+        live_id: u64,
+        left_at: DateTime<Local>,
+    },
+}
+
+impl Database {
+    fn apply(&self, write: &PendingWrite) -> Result<(), DatabaseError> {
+        match write {
+            PendingWrite::InsertListener(entity) => {
+                if (self.select_by_id(entity.id)?.is_none()) {
+                    self.insert(entity.clone())?;
+                }
+                Ok(())
+            }
+            PendingWrite::UpdateName {
+                id,
+                name,
+                tag,
+                seen_at,
+            } => self.update_name(*id, name, tag, *seen_at),
+            PendingWrite::StartVisit {
+                listener_id,
+                live_id,
+                joined_at,
+            } => self.start_visit(*listener_id, *live_id, *joined_at),
+            PendingWrite::EndVisit {
+                listener_id,
+                live_id,
+                left_at,
+            } => self.end_visit(*listener_id, *live_id, *left_at),
+            PendingWrite::EndAllVisits { live_id, left_at } => {
+                self.end_all_visits(*live_id, *left_at)
+            }
+        }
+    }
+}
+
+//how long `flush_if_due()` leaves the database alone after finding it busy
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+//keeps the writes in order so that e.g. the stay durations are not lost when the database is locked by the sqlite3 shell
+#[derive(Debug, Default)]
+pub struct WriteBuffer {
+    writes: VecDeque<PendingWrite>,
+    retry_at: Option<Instant>, //`Some` after the database was found busy
+}
+
+impl WriteBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    pub fn push(&mut self, write: PendingWrite) {
+        self.writes.push_back(write);
+    }
+
+    //applies the buffered writes in order, stopping at the first one failed temporarily
+    //The ones failed otherwise (e.g. by a constraint violation) would never succeed, so they are dropped.
+    pub fn flush(&mut self, database: &Database) -> Result<(), DatabaseError> {
+        while let Some(write) = self.writes.front() {
+            match database.apply(write) {
+                Err(e) if (e.is_busy()) => return Err(e),
+                Err(e) => error!("Dropped a write to the database {:?}: {}", write, e),
+                Ok(()) => (),
+            }
+            self.writes.pop_front();
+        }
+        Ok(())
+    }
+
+    //same as `flush()` but does nothing for `RETRY_INTERVAL` after the database was found busy,
+    // so that each write made while the database is locked does not wait for the lock again
+    pub fn flush_if_due(&mut self, database: &Database) -> Result<(), DatabaseError> {
+        self.flush_if_due_at(database, Instant::now())
+    }
+
+    fn flush_if_due_at(&mut self, database: &Database, now: Instant) -> Result<(), DatabaseError> {
+        if (self.retry_at.is_some_and(|t| now < t)) {
+            return Ok(());
+        }
+        let ret = self.flush(database);
+        self.retry_at = ret.is_err().then(|| now + RETRY_INTERVAL);
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rusqlite::Connection;
+
+    use super::super::from_timestamp;
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let path = std::env::temp_dir().join("spoon_comment_viewer_pending_test01.sqlite3");
+        let _ = fs::remove_file(&path);
+        let path = path.to_str().unwrap();
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+
+        let db = Database::new(Some(path)).unwrap();
+        db.conn.busy_timeout(Duration::ZERO).unwrap();
+        db.insert_live(10, t(0)).unwrap();
+
+        let mut buffer = WriteBuffer::new();
+        let entity =
+            ListenerEntity::new(1, "Mike".to_string(), "mike".to_string(), 0, Duration::ZERO);
+        buffer.push(PendingWrite::InsertListener(entity.clone()));
+        buffer.push(PendingWrite::StartVisit {
+            listener_id: 1,
+            live_id: 10,
+            joined_at: t(0),
+        });
+
+        //e.g. the sqlite3 shell is writing to the database
+        let other = Connection::open(path).unwrap();
+        other.execute_batch("BEGIN EXCLUSIVE;").unwrap();
+        let e = buffer.flush(&db).unwrap_err();
+        assert!(e.is_busy());
+        assert_eq!(2, buffer.len());
+
+        other.execute_batch("COMMIT;").unwrap();
+        buffer.push(PendingWrite::EndVisit {
+            listener_id: 1,
+            live_id: 10,
+            left_at: t(30),
+        });
+        //inserted only once
+        buffer.push(PendingWrite::InsertListener(entity));
+        buffer.flush(&db).unwrap();
+        assert!(buffer.is_empty());
+        assert_eq!(
+            Some(ListenerEntity::new(
+                1,
+                "Mike".to_string(),
+                "mike".to_string(),
+                1,
+                Duration::from_secs(30),
+            )),
+            db.select_by_id(1).unwrap()
+        );
+
+        drop(db);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let path = std::env::temp_dir().join("spoon_comment_viewer_pending_test02.sqlite3");
+        let _ = fs::remove_file(&path);
+        let path = path.to_str().unwrap();
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+
+        let db = Database::new(Some(path)).unwrap();
+        db.set_busy_timeout(super::super::LIVE_BUSY_TIMEOUT)
+            .unwrap();
+        let mut buffer = WriteBuffer::new();
+        let start_visit = |listener_id: usize| PendingWrite::StartVisit {
+            listener_id,
+            live_id: 10,
+            joined_at: t(0),
+        };
+
+        let other = Connection::open(path).unwrap();
+        other.execute_batch("BEGIN EXCLUSIVE;").unwrap();
+        let now = Instant::now();
+        buffer.push(start_visit(1));
+        assert!(buffer.flush_if_due_at(&db, now).unwrap_err().is_busy());
+        assert!(now.elapsed() < Duration::from_secs(1));
+
+        //not retried until `RETRY_INTERVAL` passes even if the database is unlocked
+        other.execute_batch("COMMIT;").unwrap();
+        buffer.push(start_visit(2));
+        buffer
+            .flush_if_due_at(&db, now + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(2, buffer.len());
+        buffer.flush_if_due_at(&db, now + RETRY_INTERVAL).unwrap();
+        assert!(buffer.is_empty());
+        assert_eq!(2, db.select_visits_by_live(10).unwrap().len());
+
+        drop(db);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Local};
+use rusqlite::{params, Statement};
+
+use super::{Database, DatabaseError};
+
+//an entry of the pronunciation dictionary used by the text-to-speech (see `pronunciation.rs`)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PronunciationEntity {
+    pub pattern: String,
+    pub reading: String,
+    pub is_regex: bool, //`pattern` is matched literally if `false`
+}
+
+impl Database {
+    //overwrites the entry of the same pattern
+    pub fn upsert_pronunciation(
+        &self,
+        entity: &PronunciationEntity,
+        created_at: DateTime<Local>,
+    ) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO pronunciations (pattern, reading, is_regex, created_at) VALUES (?, ?, ?, ?);",
+            params![
+                entity.pattern,
+                entity.reading,
+                entity.is_regex,
+                created_at.timestamp(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    //returns `false` if there is no such entry
+    pub fn delete_pronunciation(&self, pattern: &str) -> Result<bool, DatabaseError> {
+        let n = self
+            .conn
+            .execute("DELETE FROM pronunciations WHERE pattern = ?;", [pattern])?;
+        Ok(n == 1)
+    }
+
+    //in the order the entries are applied (the oldest first)
+    pub fn select_pronunciations(&self) -> Result<Vec<PronunciationEntity>, DatabaseError> {
+        let mut statement: Statement = self.conn.prepare(
+            "SELECT pattern, reading, is_regex FROM pronunciations ORDER BY created_at, rowid;",
+        )?;
+        let ret = statement
+            .query_map([], |r| {
+                Ok(PronunciationEntity {
+                    pattern: r.get(0)?,
+                    reading: r.get(1)?,
+                    is_regex: r.get(2)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::from_timestamp;
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+        let entity = |pattern: &str, reading: &str, is_regex: bool| PronunciationEntity {
+            pattern: pattern.to_string(),
+            reading: reading.to_string(),
+            is_regex,
+        };
+
+        db.upsert_pronunciation(&entity("草", "くさ", false), t(0))
+            .unwrap();
+        db.upsert_pronunciation(&entity("^おは$", "おはよう", true), t(1))
+            .unwrap();
+        db.upsert_pronunciation(&entity("草", "わら", false), t(2))
+            .unwrap();
+        assert_eq!(
+            vec![
+                entity("^おは$", "おはよう", true),
+                entity("草", "わら", false)
+            ],
+            db.select_pronunciations().unwrap()
+        );
+        assert!(db.delete_pronunciation("草").unwrap());
+        assert!(!db.delete_pronunciation("草").unwrap());
+        assert_eq!(
+            vec![entity("^おは$", "おはよう", true)],
+            db.select_pronunciations().unwrap()
+        );
+    }
+}
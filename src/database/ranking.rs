@@ -0,0 +1,122 @@
+use rusqlite::{params, Statement};
+
+use super::{Database, DatabaseError, ListenerEntity};
+
+//The ranking is by `stay_duration`, then by `visit_count`, both in descending order.
+//Ties are broken by `id` (the user id of Spoon) in ascending order so that the ranks are unique.
+//Note the user id doesn't tell which listener came first.
+impl Database {
+    pub fn count(&self) -> Result<usize, DatabaseError> {
+        Ok(self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM {};", self.table_name),
+            [],
+            |r| r.get(0),
+        )?)
+    }
+
+    //1-based rank of the listener, which is `None` if not found
+    pub fn select_rank(&self, id: usize) -> Result<Option<usize>, DatabaseError> {
+        let entity = match self.select_by_id(id)? {
+            None => return Ok(None),
+            Some(e) => e,
+        };
+        let num_ahead: usize = self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM {} WHERE
+                        stay_duration > ?1
+                        OR (stay_duration = ?1 AND visit_count > ?2)
+                        OR (stay_duration = ?1 AND visit_count = ?2 AND id < ?3);",
+                self.table_name
+            ),
+            params![entity.stay_duration.as_secs(), entity.visit_count, id],
+            |r| r.get(0),
+        )?;
+        Ok(Some(num_ahead + 1))
+    }
+
+    fn select_top_by(&self, order: &str, n: usize) -> Result<Vec<ListenerEntity>, DatabaseError> {
+        let mut statement: Statement = self.conn.prepare(&format!(
+            "{} ORDER BY {} LIMIT ?;",
+            self.select_statement(),
+            order
+        ))?;
+        let ret = statement
+            .query_map([n], Self::to_entity)?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+
+    //the first `n` listeners in the ranking
+    pub fn select_top(&self, n: usize) -> Result<Vec<ListenerEntity>, DatabaseError> {
+        self.select_top_by("stay_duration DESC, visit_count DESC, id", n)
+    }
+
+    //the same as `select_top()` but by `visit_count` first
+    pub fn select_top_by_visit_count(
+        &self,
+        n: usize,
+    ) -> Result<Vec<ListenerEntity>, DatabaseError> {
+        self.select_top_by("visit_count DESC, stay_duration DESC, id", n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Local;
+
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        let entity = |id: usize, visit_count: usize, stay: u64| {
+            ListenerEntity::new(
+                id,
+                format!("user{}", id),
+                "".to_string(),
+                visit_count,
+                Duration::from_secs(stay),
+            )
+        };
+        db.insert(entity(1, 1, 100)).unwrap();
+        db.insert(entity(2, 5, 100)).unwrap();
+        db.insert(entity(3, 5, 100)).unwrap();
+        db.insert(entity(4, 9, 50)).unwrap();
+
+        assert_eq!(4, db.count().unwrap());
+        assert_eq!(
+            vec![2, 3, 1, 4],
+            db.select_top(10)
+                .unwrap()
+                .iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![4, 2],
+            db.select_top_by_visit_count(2)
+                .unwrap()
+                .iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(2), db.select_rank(3).unwrap());
+        assert_eq!(Some(4), db.select_rank(4).unwrap());
+        assert_eq!(None, db.select_rank(5).unwrap());
+
+        //the triggers keep the cumulative values in sync with the visits
+        let t = Local::now();
+        db.insert_live(1, t).unwrap();
+        db.start_visit(4, 1, t).unwrap();
+        db.end_visit(4, 1, t + chrono::Duration::seconds(100))
+            .unwrap();
+        db.start_visit(4, 1, t + chrono::Duration::seconds(200))
+            .unwrap();
+        assert_eq!(Some(entity(4, 10, 150)), db.select_by_id(4).unwrap());
+        assert_eq!(Some(1), db.select_rank(4).unwrap());
+        assert_eq!(Some(4), db.select_rank(1).unwrap());
+    }
+}
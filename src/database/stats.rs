@@ -0,0 +1,197 @@
+use chrono::{DateTime, Local};
+use rusqlite::Statement;
+
+use super::{from_timestamp, Database, DatabaseError, GiftKind};
+
+//the summary of a broadcast
+#[derive(Debug, PartialEq, Clone)]
+pub struct LiveSummary {
+    pub id: u64,
+    pub started_at: DateTime<Local>,
+    pub num_listener: usize,
+    pub num_new_listener: usize, //the listeners who visited for the first time
+    pub num_retained_listener: usize, //the listeners who visited any of the later lives
+    pub num_comment: usize,
+    pub num_spoon: i64,
+}
+
+impl LiveSummary {
+    pub fn num_returning_listener(&self) -> usize {
+        self.num_listener - self.num_new_listener
+    }
+}
+
+//the gifts a listener has ever sent
+#[derive(Debug, PartialEq, Clone)]
+pub struct GiftTotal {
+    pub listener_id: usize,
+    pub name: String,
+    pub num_spoon: i64,
+    pub num_heart: i64, //including busters
+    pub num_item: i64,
+}
+
+impl Database {
+    //in chronological order
+    pub fn select_live_summaries(&self) -> Result<Vec<LiveSummary>, DatabaseError> {
+        //A listener is new in the live containing the first visit unless visited before the visits were recorded.
+        let mut statement: Statement = self
+            .conn
+            .prepare(
+                "WITH first_visits AS (
+                    SELECT listener_id, live_id FROM visits v
+                    WHERE id = (SELECT id FROM visits w WHERE w.listener_id = v.listener_id ORDER BY joined_at, id LIMIT 1)
+                )
+                SELECT
+                    lv.id,
+                    lv.started_at,
+                    (SELECT COUNT(DISTINCT listener_id) FROM visits WHERE live_id = lv.id),
+                    (
+                        SELECT COUNT(*) FROM first_visits f LEFT JOIN listeners l ON l.id = f.listener_id
+                        WHERE f.live_id = lv.id AND IFNULL(l.legacy_visit_count, 0) = 0
+                    ),
+                    (
+                        SELECT COUNT(DISTINCT v.listener_id) FROM visits v
+                        WHERE v.live_id = lv.id AND EXISTS (
+                            SELECT 1 FROM visits w JOIN lives later ON later.id = w.live_id
+                            WHERE w.listener_id = v.listener_id AND later.started_at > lv.started_at
+                        )
+                    ),
+                    (SELECT COUNT(*) FROM comments WHERE live_id = lv.id),
+                    (SELECT IFNULL(SUM(amount * combo), 0) FROM gifts WHERE live_id = lv.id AND kind = 'spoon')
+                FROM lives lv
+                ORDER BY lv.started_at, lv.id;",
+            )?;
+        let ret = statement
+            .query_map([], |r| {
+                Ok(LiveSummary {
+                    id: r.get(0)?,
+                    started_at: from_timestamp(r.get(1)?),
+                    num_listener: r.get(2)?,
+                    num_new_listener: r.get(3)?,
+                    num_retained_listener: r.get(4)?,
+                    num_comment: r.get(5)?,
+                    num_spoon: r.get(6)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+
+    //the top `n` listeners by the number of spoons
+    pub fn select_gift_totals(&self, n: usize) -> Result<Vec<GiftTotal>, DatabaseError> {
+        let mut statement: Statement = self
+            .conn
+            .prepare(&format!(
+                "SELECT
+                    g.listener_id,
+                    IFNULL(l.name, ''),
+                    IFNULL(SUM(CASE WHEN g.kind = '{}' THEN g.amount * g.combo END), 0) AS num_spoon,
+                    IFNULL(SUM(CASE WHEN g.kind IN ('{}', '{}') THEN g.amount * g.combo END), 0),
+                    IFNULL(SUM(CASE WHEN g.kind = '{}' THEN g.amount END), 0)
+                FROM gifts g LEFT JOIN listeners l ON l.id = g.listener_id
+                GROUP BY g.listener_id
+                ORDER BY num_spoon DESC, g.listener_id
+                LIMIT ?;",
+                GiftKind::Spoon.as_str(),
+                GiftKind::Heart.as_str(),
+                GiftKind::Buster.as_str(),
+                GiftKind::Item.as_str(),
+            ))?;
+        let ret = statement
+            .query_map([n], |r| {
+                Ok(GiftTotal {
+                    listener_id: r.get(0)?,
+                    name: r.get(1)?,
+                    num_spoon: r.get(2)?,
+                    num_heart: r.get(3)?,
+                    num_item: r.get(4)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::{GiftEntity, ListenerEntity};
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+
+        //1: a regular from before the visits were recorded, 2 and 3: new
+        for (id, visit_count) in [(1, 5), (2, 0), (3, 0)] {
+            db.insert(ListenerEntity::new(
+                id,
+                format!("user{}", id),
+                "".to_string(),
+                visit_count,
+                Duration::ZERO,
+            ))
+            .unwrap();
+        }
+
+        db.insert_live(10, t(0)).unwrap();
+        db.start_visit(1, 10, t(0)).unwrap();
+        db.start_visit(2, 10, t(0)).unwrap();
+        db.start_visit(2, 10, t(10)).unwrap(); //rejoin
+        db.insert_comment(2, 10, "hello", t(1)).unwrap();
+        db.insert_gift(&GiftEntity::new(1, 10, GiftKind::Spoon, "", 10, 2, t(2)))
+            .unwrap();
+        db.insert_gift(&GiftEntity::new(2, 10, GiftKind::Heart, "", 1, 1, t(3)))
+            .unwrap();
+        db.insert_gift(&GiftEntity::new(2, 10, GiftKind::Buster, "", 10, 3, t(4)))
+            .unwrap();
+
+        db.insert_live(11, t(100)).unwrap();
+        db.start_visit(2, 11, t(100)).unwrap();
+        db.start_visit(3, 11, t(100)).unwrap();
+        db.insert_gift(&GiftEntity::new(3, 11, GiftKind::Item, "34", 1, 1, t(101)))
+            .unwrap();
+
+        let summaries = db.select_live_summaries().unwrap();
+        assert_eq!(2, summaries.len());
+        assert_eq!(
+            LiveSummary {
+                id: 10,
+                started_at: t(0),
+                num_listener: 2,
+                num_new_listener: 1,
+                num_retained_listener: 1,
+                num_comment: 1,
+                num_spoon: 20,
+            },
+            summaries[0]
+        );
+        assert_eq!(1, summaries[0].num_returning_listener());
+        assert_eq!(
+            (2, 1, 0),
+            (
+                summaries[1].num_listener,
+                summaries[1].num_new_listener,
+                summaries[1].num_retained_listener
+            )
+        );
+
+        let totals = db.select_gift_totals(10).unwrap();
+        assert_eq!(3, totals.len());
+        assert_eq!(
+            (1, 20, 0),
+            (
+                totals[0].listener_id,
+                totals[0].num_spoon,
+                totals[0].num_heart
+            )
+        );
+        assert_eq!((2, 31), (totals[1].listener_id, totals[1].num_heart));
+        assert_eq!((3, 1), (totals[2].listener_id, totals[2].num_item));
+        assert_eq!(1, db.select_gift_totals(1).unwrap().len());
+    }
+}
@@ -0,0 +1,319 @@
+use chrono::{DateTime, Local};
+use rusqlite::{params, Row, Statement};
+
+use super::{from_timestamp, Database, DatabaseError};
+
+//a broadcast
+#[derive(Debug, PartialEq, Clone)]
+pub struct LiveEntity {
+    pub id: u64,
+    pub started_at: DateTime<Local>,
+    pub ended_at: Option<DateTime<Local>>, //`None` while on air (or if the bot was killed)
+}
+
+//a stay of a listener in a broadcast, from the join to the leave
+#[derive(Debug, PartialEq, Clone)]
+pub struct VisitEntity {
+    pub id: i64,
+    pub listener_id: usize,
+    pub live_id: u64,
+    pub joined_at: DateTime<Local>,
+    pub left_at: Option<DateTime<Local>>, //`None` while staying
+}
+
+impl LiveEntity {
+    fn from_row(r: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: r.get(0)?,
+            started_at: from_timestamp(r.get(1)?),
+            ended_at: r.get::<_, Option<i64>>(2)?.map(from_timestamp),
+        })
+    }
+}
+
+impl VisitEntity {
+    fn from_row(r: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: r.get(0)?,
+            listener_id: r.get(1)?,
+            live_id: r.get(2)?,
+            joined_at: from_timestamp(r.get(3)?),
+            left_at: r.get::<_, Option<i64>>(4)?.map(from_timestamp),
+        })
+    }
+}
+
+impl Database {
+    //Restarting the bot during a live keeps the original start time.
+    pub fn insert_live(
+        &self,
+        live_id: u64,
+        started_at: DateTime<Local>,
+    ) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO lives (id, started_at) VALUES (?, ?);",
+            params![live_id, started_at.timestamp()],
+        )?;
+        Ok(())
+    }
+
+    pub fn end_live(&self, live_id: u64, ended_at: DateTime<Local>) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "UPDATE lives SET ended_at = ? WHERE id = ?;",
+            params![ended_at.timestamp(), live_id],
+        )?;
+        Ok(())
+    }
+
+    //in chronological order
+    pub fn select_lives(&self) -> Result<Vec<LiveEntity>, DatabaseError> {
+        let mut statement: Statement = self
+            .conn
+            .prepare("SELECT id, started_at, ended_at FROM lives ORDER BY started_at, id;")?;
+        let ret = statement
+            .query_map([], LiveEntity::from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+
+    pub fn start_visit(
+        &self,
+        listener_id: usize,
+        live_id: u64,
+        joined_at: DateTime<Local>,
+    ) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT INTO visits (listener_id, live_id, joined_at) VALUES (?, ?, ?);",
+            params![listener_id, live_id, joined_at.timestamp()],
+        )?;
+        Ok(())
+    }
+
+    pub fn end_visit(
+        &self,
+        listener_id: usize,
+        live_id: u64,
+        left_at: DateTime<Local>,
+    ) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "UPDATE visits SET left_at = ? WHERE listener_id = ? AND live_id = ? AND left_at IS NULL;",
+            params![left_at.timestamp(), listener_id, live_id],
+        )?;
+        Ok(())
+    }
+
+    //ends the visits of all the listeners still staying in the live (e.g. when the live ends)
+    pub fn end_all_visits(
+        &self,
+        live_id: u64,
+        left_at: DateTime<Local>,
+    ) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "UPDATE visits SET left_at = ? WHERE live_id = ? AND left_at IS NULL;",
+            params![left_at.timestamp(), live_id],
+        )?;
+        Ok(())
+    }
+
+    //ends the visits left open in the lives other than `live_id` (i.e. the bot was killed without ending them),
+    // at the end of the live or, if that is unknown too, at the last activity in the live,
+    // so that the time the bot was down is not added to `stay_duration`
+    //This returns the number of the visits ended.
+    pub fn end_stale_visits(&self, live_id: u64) -> Result<usize, DatabaseError> {
+        let ret = self.conn.execute(
+            "UPDATE visits SET left_at = MAX(
+                joined_at,
+                COALESCE(
+                    (SELECT ended_at FROM lives WHERE lives.id = visits.live_id),
+                    MAX(
+                        (SELECT MAX(COALESCE(v.left_at, v.joined_at)) FROM visits AS v WHERE v.live_id = visits.live_id),
+                        COALESCE((SELECT MAX(created_at) FROM comments WHERE comments.live_id = visits.live_id), 0),
+                        COALESCE((SELECT MAX(created_at) FROM gifts WHERE gifts.live_id = visits.live_id), 0)
+                    )
+                )
+            )
+            WHERE live_id != ? AND left_at IS NULL;",
+            [live_id],
+        )?;
+        Ok(ret)
+    }
+
+    fn select_visits(&self, condition: &str, id: i64) -> Result<Vec<VisitEntity>, DatabaseError> {
+        let mut statement: Statement = self
+            .conn
+            .prepare(&format!(
+                "SELECT id, listener_id, live_id, joined_at, left_at FROM visits WHERE {} = ? ORDER BY joined_at, id;",
+                condition
+            ))?;
+        let ret = statement
+            .query_map([id], VisitEntity::from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(ret)
+    }
+
+    //who was in the live, in the order of joining
+    pub fn select_visits_by_live(&self, live_id: u64) -> Result<Vec<VisitEntity>, DatabaseError> {
+        self.select_visits("live_id", live_id as i64)
+    }
+
+    //the visits of the listener in chronological order, the first of which is the first visit
+    pub fn select_visits_by_listener(
+        &self,
+        listener_id: usize,
+    ) -> Result<Vec<VisitEntity>, DatabaseError> {
+        self.select_visits("listener_id", listener_id as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::ListenerEntity;
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+
+        //visited twice before the visits were recorded
+        db.insert(ListenerEntity::new(
+            1,
+            "Mike".to_string(),
+            "mike".to_string(),
+            2,
+            Duration::from_secs(100),
+        ))
+        .unwrap();
+        db.insert(ListenerEntity::new(
+            2,
+            "Lisa".to_string(),
+            "lisa".to_string(),
+            0,
+            Duration::ZERO,
+        ))
+        .unwrap();
+
+        db.insert_live(10, t(0)).unwrap();
+        db.start_visit(1, 10, t(10)).unwrap();
+        db.start_visit(2, 10, t(20)).unwrap();
+        db.end_visit(1, 10, t(40)).unwrap();
+        //rejoins in the same live
+        db.start_visit(1, 10, t(50)).unwrap();
+        db.end_all_visits(10, t(60)).unwrap();
+        db.end_live(10, t(60)).unwrap();
+
+        db.insert_live(11, t(100)).unwrap();
+        db.start_visit(2, 11, t(100)).unwrap();
+
+        assert_eq!(
+            vec![
+                LiveEntity {
+                    id: 10,
+                    started_at: t(0),
+                    ended_at: Some(t(60)),
+                },
+                LiveEntity {
+                    id: 11,
+                    started_at: t(100),
+                    ended_at: None,
+                },
+            ],
+            db.select_lives().unwrap()
+        );
+        assert_eq!(
+            vec![1, 2, 1],
+            db.select_visits_by_live(10)
+                .unwrap()
+                .iter()
+                .map(|e| e.listener_id)
+                .collect::<Vec<_>>()
+        );
+        let visits = db.select_visits_by_listener(2).unwrap();
+        assert_eq!(2, visits.len());
+        assert_eq!(t(20), visits[0].joined_at);
+        assert_eq!(None, visits[1].left_at);
+
+        let mike = db.select_by_id(1).unwrap().unwrap();
+        assert_eq!(3, mike.visit_count);
+        assert_eq!(Duration::from_secs(100 + 30 + 10), mike.stay_duration);
+        //The ongoing visit is not counted in `stay_duration`.
+        let lisa = db.select_by_id(2).unwrap().unwrap();
+        assert_eq!(2, lisa.visit_count);
+        assert_eq!(Duration::from_secs(40), lisa.stay_duration);
+
+        //overwriting the cumulative values keeps the visits
+        db.update(ListenerEntity {
+            visit_count: 5,
+            stay_duration: Duration::from_secs(1000),
+            ..mike
+        })
+        .unwrap();
+        db.start_visit(1, 11, t(100)).unwrap();
+        db.end_visit(1, 11, t(110)).unwrap();
+        let mike = db.select_by_id(1).unwrap().unwrap();
+        assert_eq!(6, mike.visit_count);
+        assert_eq!(Duration::from_secs(1010), mike.stay_duration);
+
+        db.update_name(1, "Bob", "bob", t(120)).unwrap();
+        let bob = db.select_by_id(1).unwrap().unwrap();
+        assert_eq!(
+            ("Bob", "bob", 6),
+            (bob.name.as_str(), bob.tag.as_str(), bob.visit_count)
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let db = Database::new(None).unwrap();
+        let t = |s: i64| from_timestamp(1_700_000_000 + s);
+        for id in 1..=3 {
+            db.insert(ListenerEntity::new(
+                id,
+                id.to_string(),
+                id.to_string(),
+                0,
+                Duration::ZERO,
+            ))
+            .unwrap();
+        }
+
+        //killed in the middle of the live 10, which never ended
+        db.insert_live(10, t(0)).unwrap();
+        db.start_visit(1, 10, t(10)).unwrap();
+        db.start_visit(2, 10, t(20)).unwrap();
+        db.end_visit(2, 10, t(30)).unwrap();
+        db.insert_comment(3, 10, "hello", t(50)).unwrap();
+        //killed after the live 11 ended
+        db.insert_live(11, t(100)).unwrap();
+        db.start_visit(3, 11, t(110)).unwrap();
+        db.end_live(11, t(120)).unwrap();
+
+        //days later
+        db.insert_live(12, t(100_000)).unwrap();
+        db.start_visit(2, 12, t(100_000)).unwrap();
+        //Leaving the live 12 does not end the visit in the live 10.
+        db.end_visit(1, 12, t(100_010)).unwrap();
+        assert_eq!(None, db.select_visits_by_listener(1).unwrap()[0].left_at);
+
+        assert_eq!(2, db.end_stale_visits(12).unwrap());
+        assert_eq!(
+            Some(t(50)),
+            db.select_visits_by_listener(1).unwrap()[0].left_at
+        );
+        assert_eq!(
+            Some(t(120)),
+            db.select_visits_by_listener(3).unwrap()[0].left_at
+        );
+        //The current live is left as it is.
+        assert_eq!(None, db.select_visits_by_live(12).unwrap()[0].left_at);
+        assert_eq!(
+            Duration::from_secs(40),
+            db.select_by_id(1).unwrap().unwrap().stay_duration
+        );
+        assert_eq!(0, db.end_stale_visits(12).unwrap());
+    }
+}
@@ -0,0 +1,53 @@
+use rusqlite::params;
+
+use super::{Database, DatabaseError};
+
+impl Database {
+    //`voice` is the name of a voice such as `zundamon` (see `config.voicevox.voices`)
+    pub fn set_voice(&self, listener_id: usize, voice: &str) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO listener_voices (listener_id, voice) VALUES (?, ?);",
+            params![listener_id, voice],
+        )?;
+        Ok(())
+    }
+
+    //returns `false` if the listener has not chosen a voice
+    pub fn remove_voice(&self, listener_id: usize) -> Result<bool, DatabaseError> {
+        let n = self.conn.execute(
+            "DELETE FROM listener_voices WHERE listener_id = ?;",
+            [listener_id],
+        )?;
+        Ok(n == 1)
+    }
+
+    pub fn select_voice(&self, listener_id: usize) -> Result<Option<String>, DatabaseError> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT voice FROM listener_voices WHERE listener_id = ?;")?;
+        let voices = statement
+            .query_map([listener_id], |r| r.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(voices.into_iter().next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        assert_eq!(None, db.select_voice(1).unwrap());
+        db.set_voice(1, "zundamon").unwrap();
+        db.set_voice(1, "sayo").unwrap();
+        db.set_voice(2, "asmr").unwrap();
+        assert_eq!(Some("sayo".to_string()), db.select_voice(1).unwrap());
+        assert!(db.remove_voice(1).unwrap());
+        assert!(!db.remove_voice(1).unwrap());
+        assert_eq!(None, db.select_voice(1).unwrap());
+        assert_eq!(Some("asmr".to_string()), db.select_voice(2).unwrap());
+    }
+}
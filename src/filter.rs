@@ -27,6 +27,7 @@ impl Filter {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)] //`assert_eq!(true, ..)` reads better beside `assert_eq!(false, ..)`
 mod tests {
     use std::time::Instant;
 
@@ -35,14 +36,14 @@ mod tests {
     #[test]
     // #[ignore]
     fn test01() {
-        let filter = Filter::new(&vec![]);
+        let filter = Filter::new(&[]);
         assert_eq!(true, filter.is_normal("あいうえお"));
     }
 
     #[test]
     // #[ignore]
     fn test02() {
-        let filter = Filter::new(&vec!["りんご".to_string(), "ゴリラ".to_string()]);
+        let filter = Filter::new(&["りんご".to_string(), "ゴリラ".to_string()]);
         assert_eq!(true, filter.is_normal("あいうえお"));
         assert_eq!(false, filter.is_normal("ありんごい"));
         assert_eq!(false, filter.is_normal("あゴリラい"));
@@ -52,7 +53,7 @@ mod tests {
     #[test]
     // #[ignore]
     fn test03() {
-        let filter = Filter::new(&vec!["りんご".to_string(), "ボックス".to_string()]);
+        let filter = Filter::new(&["りんご".to_string(), "ボックス".to_string()]);
         assert_eq!("あいうえお".to_string(), filter.sanitize("あいうえお"));
         assert_eq!("あ***い".to_string(), filter.sanitize("ありんごい"));
         assert_eq!(
@@ -68,7 +69,7 @@ mod tests {
     // #[ignore]
     fn test04() {
         //forbidden words are cited from |https://ryoko-club.com/food/|
-        let filter = Filter::new(&vec![
+        let filter = Filter::new(&[
             "柿".to_string(),
             "桃".to_string(),
             "梅".to_string(),
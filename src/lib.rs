@@ -1,16 +1,29 @@
+pub mod archive;
+pub mod backend;
 pub mod bgm;
 pub mod chatgpt;
+pub mod command;
+pub mod comment_queue;
 pub mod config;
 pub mod constant;
 pub mod database;
 pub mod filter;
 pub mod listener;
+pub mod locale;
 pub mod logger;
+pub mod loyalty;
+pub mod message_catalog;
 pub mod models;
+pub mod notes;
 pub mod player;
+pub mod pronunciation;
 pub mod selenium;
 pub mod spoon_client;
 pub mod spoon_core;
+pub mod stats;
+#[cfg(test)]
+mod test_server;
+pub mod tts;
 pub mod util;
 pub mod voicevox;
 pub mod websocket;
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+//the language of the bot's output
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    Ja,
+    En,
+    Ko,
+}
+
+impl Locale {
+    //the key used in a message catalog
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::Ja => "ja",
+            Locale::En => "en",
+            Locale::Ko => "ko",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "ja" => Some(Locale::Ja),
+            "en" => Some(Locale::En),
+            "ko" => Some(Locale::Ko),
+            _ => None,
+        }
+    }
+
+    //guesses a locale from the `country` field (e.g. `jp`) of a user in the Spoon events
+    //`None` is returned when the country is unknown.
+    pub fn from_country(country: &str) -> Option<Self> {
+        match country.to_lowercase().as_str() {
+            "" => None,
+            "jp" => Some(Locale::Ja),
+            "kr" => Some(Locale::Ko),
+            _ => Some(Locale::En),
+        }
+    }
+
+    //(hour, minute, second)
+    pub fn duration_units(&self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            Locale::Ja => ("時間", "分", "秒"),
+            Locale::En => ("h", "m", "s"),
+            Locale::Ko => ("시간", "분", "초"),
+        }
+    }
+
+    pub fn pretty_print_duration(&self, d: Duration) -> String {
+        let (hour_unit, min_unit, sec_unit) = self.duration_units();
+        let s = d.as_secs();
+        if (s <= 60) {
+            format!("{}{}", s, sec_unit)
+        } else if (s <= 3600) {
+            let min = s / 60;
+            let sec = s - min * 60;
+            format!("{}{}{:02}{}", min, min_unit, sec, sec_unit)
+        } else {
+            let hour = s / 3600;
+            let min = (s - hour * 3600) / 60;
+            let sec = s - hour * 3600 - min * 60;
+            format!(
+                "{}{}{:02}{}{:02}{}",
+                hour, hour_unit, min, min_unit, sec, sec_unit
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let d = Duration::from_secs(4215);
+        assert_eq!("1時間10分15秒", Locale::Ja.pretty_print_duration(d));
+        assert_eq!("1h10m15s", Locale::En.pretty_print_duration(d));
+        assert_eq!("1시간10분15초", Locale::Ko.pretty_print_duration(d));
+        assert_eq!(
+            "3s",
+            Locale::En.pretty_print_duration(Duration::from_secs(3))
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        assert_eq!(Some(Locale::Ja), Locale::from_country("jp"));
+        assert_eq!(Some(Locale::Ko), Locale::from_country("KR"));
+        assert_eq!(Some(Locale::En), Locale::from_country("us"));
+        assert_eq!(None, Locale::from_country(""));
+        for locale in [Locale::Ja, Locale::En, Locale::Ko] {
+            assert_eq!(Some(locale), Locale::from_code(locale.code()));
+        }
+    }
+}
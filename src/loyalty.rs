@@ -0,0 +1,96 @@
+use super::config::{Loyalty, Tier};
+use super::database::{ListenerEntity, Milestone};
+
+fn stay_hours(entity: &ListenerEntity) -> u64 {
+    entity.stay_duration.as_secs() / 3600
+}
+
+//the highest tier the listener has reached
+pub fn tier<'a>(config: &'a Loyalty, entity: &ListenerEntity) -> Option<&'a Tier> {
+    config
+        .tiers
+        .iter()
+        .filter(|tier| stay_hours(entity) >= tier.min_stay_hours)
+        .max_by_key(|tier| tier.min_stay_hours)
+}
+
+//all the milestones of visits and stays the listener has reached, whether or not already celebrated
+pub fn reached_milestones(config: &Loyalty, entity: &ListenerEntity) -> Vec<Milestone> {
+    let visit_counts = config
+        .visit_count_milestones
+        .iter()
+        .filter(|n| entity.visit_count >= **n)
+        .map(|n| Milestone::VisitCount(*n));
+    let stay_hours = config
+        .stay_hours_milestones
+        .iter()
+        .filter(|n| stay_hours(entity) >= **n)
+        .map(|n| Milestone::StayHours(*n));
+    visit_counts.chain(stay_hours).collect()
+}
+
+//Only the largest one of each kind is worth celebrating when several are achieved at once
+// (e.g. a regular's first visit after the loyalty program is enabled).
+pub fn milestones_to_celebrate(new_milestones: &[Milestone]) -> Vec<Milestone> {
+    new_milestones
+        .iter()
+        .filter(|m| !new_milestones.iter().any(|other| m.is_exceeded_by(other)))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let config = Loyalty {
+            enabled: true,
+            tiers: vec![
+                Tier {
+                    name: "gold".to_string(),
+                    min_stay_hours: 100,
+                },
+                Tier {
+                    name: "bronze".to_string(),
+                    min_stay_hours: 10,
+                },
+            ],
+            visit_count_milestones: vec![10, 50],
+            stay_hours_milestones: vec![10, 100],
+            ..Default::default()
+        };
+        let entity = |visit_count: usize, hours: u64| {
+            ListenerEntity::new(
+                1,
+                "Mike".to_string(),
+                "mike".to_string(),
+                visit_count,
+                Duration::from_secs(hours * 3600 + 59),
+            )
+        };
+
+        assert!(tier(&config, &entity(1, 9)).is_none());
+        assert_eq!("bronze", tier(&config, &entity(1, 99)).unwrap().name);
+        assert_eq!("gold", tier(&config, &entity(1, 100)).unwrap().name);
+
+        assert!(reached_milestones(&config, &entity(9, 9)).is_empty());
+        let milestones = reached_milestones(&config, &entity(50, 10));
+        assert_eq!(
+            vec![
+                Milestone::VisitCount(10),
+                Milestone::VisitCount(50),
+                Milestone::StayHours(10),
+            ],
+            milestones
+        );
+        assert_eq!(
+            vec![Milestone::VisitCount(50), Milestone::StayHours(10)],
+            milestones_to_celebrate(&milestones)
+        );
+    }
+}
@@ -6,17 +6,74 @@ use std::sync::mpsc;
 use std::thread;
 use std::time::{Duration, Instant};
 
+use chrono::Local;
 use log::error;
 
+use spoon_comment_viewer::archive;
+use spoon_comment_viewer::backend::FakeBackend;
 use spoon_comment_viewer::config::Config;
+use spoon_comment_viewer::database::Database;
+use spoon_comment_viewer::notes;
+use spoon_comment_viewer::pronunciation;
 use spoon_comment_viewer::spoon_client::SpoonClient;
+use spoon_comment_viewer::stats;
 
 const CONFIG_FILE: &str = "./config.json";
 
+//replays a capture file offline: nothing is sent to Spoon, the database is in memory, and ChatGPT and reading aloud are disabled
+//The comments the bot would have posted are printed at the end.
+fn replay(mut config: Config, path: &str) -> Result<(), Box<dyn Error>> {
+    config.database_file = ":memory:".to_string();
+    config.chatgpt.enabled = false;
+    config.voicevox.enabled = false;
+    let spoon = Rc::new(FakeBackend::new(0));
+    let mut client = SpoonClient::with_backend(Rc::new(config), spoon.clone())?;
+    client.replay(path)?;
+    for s in spoon.comments() {
+        println!("{}", s);
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     env::set_var("RUST_LOG", "info");
     env_logger::init();
 
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if let Some(mode) = args.first() {
+        let config = Config::new(CONFIG_FILE);
+        if let ("replay", [path]) = (mode.as_str(), &args[1..]) {
+            return replay(config, path);
+        }
+        let database = Database::new(Some(&config.database_file))?;
+        match (mode.as_str(), &args[1..]) {
+            ("stats", args) => {
+                let options = stats::Options::parse(args)?;
+                print!("{}", stats::report(&database, &options)?);
+            }
+            ("export", args) => {
+                let format = archive::parse_format(args)?;
+                print!("{}", archive::export(&database, format)?);
+            }
+            ("import", [path]) => {
+                let summary = database.merge(path)?;
+                println!("{:?}", summary);
+            }
+            ("backup", [path]) => database.backup(path)?,
+            (mode, _) if (notes::COMMANDS.contains(&mode)) => {
+                println!("{}", notes::execute(&database, &args, Local::now())?);
+            }
+            (mode, _) if (pronunciation::COMMANDS.contains(&mode)) => {
+                println!(
+                    "{}",
+                    pronunciation::execute(&database, &args, Local::now())?
+                );
+            }
+            _ => return Err(format!("unknown arguments {:?}", args).into()),
+        }
+        return Ok(());
+    }
+
     let (tx, rx) = mpsc::channel();
 
     ctrlc::set_handler(move || {
@@ -26,7 +83,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let config = Rc::new(Config::new(CONFIG_FILE));
 
-    let mut spoon = SpoonClient::new(config.clone());
+    let mut spoon = SpoonClient::new(config.clone())?;
     spoon.login(
         &config.spoon.url,
         &config.twitter.id,
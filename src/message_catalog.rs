@@ -0,0 +1,154 @@
+use std::{collections::HashMap, error::Error, fs};
+
+use rand::seq::SliceRandom;
+use regex::{Captures, Regex};
+
+use super::locale::Locale;
+
+//the catalog shipped with the bot, which is also used for the keys missing in a user's catalog
+const DEFAULT_CATALOG: &str = include_str!("../messages_template.json");
+
+type Messages = HashMap<String, Vec<String>>;
+
+//This struct holds the phrases the bot says.
+//A catalog is a JSON object which maps a locale (e.g. `ja`) to the messages in that language,
+// and the messages map a key (e.g. `welcome`) to one or more variants.
+//Each variant may contain placeholders such as `{nickname}`.
+//When a message is rendered, one of the variants is chosen at random and the placeholders are substituted.
+pub struct MessageCatalog {
+    locale: Locale, //used when no locale is specified or a message is missing in the specified locale
+    messages: HashMap<Locale, Messages>,
+    placeholder_regex: Regex,
+}
+
+impl MessageCatalog {
+    //`None` or an empty path means the default catalog.
+    pub fn new(catalog_file: Option<&str>, locale: Locale) -> Result<Self, Box<dyn Error>> {
+        let mut messages = Self::parse(DEFAULT_CATALOG)?;
+        if let Some(path) = catalog_file.filter(|p| !p.is_empty()) {
+            let user_catalog = Self::parse(&fs::read_to_string(path)?)
+                .map_err(|e| format!("invalid message catalog [ {} ]: {}", path, e))?;
+            for (locale, m) in user_catalog {
+                messages.entry(locale).or_default().extend(m);
+            }
+        }
+        Ok(Self {
+            locale,
+            messages,
+            placeholder_regex: Regex::new(r#"\{(\w+)\}"#).unwrap(),
+        })
+    }
+
+    fn parse(s: &str) -> Result<HashMap<Locale, Messages>, Box<dyn Error>> {
+        let catalog: HashMap<String, Messages> = serde_json::from_str(s)?;
+        let mut ret = HashMap::new();
+        for (code, messages) in catalog {
+            let locale = match Locale::from_code(&code) {
+                None => return Err(format!("unknown locale `{}`", code).into()),
+                Some(l) => l,
+            };
+            if let Some((key, _)) = messages.iter().find(|(_, v)| v.is_empty()) {
+                return Err(format!("no variant is specified for `{}.{}`", code, key).into());
+            }
+            ret.insert(locale, messages);
+        }
+        Ok(ret)
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    //`params` is a list of `(placeholder name, value)`.
+    pub fn render(&self, key: &str, params: &[(&str, &str)]) -> String {
+        self.render_in(self.locale, key, params)
+    }
+
+    //Unknown keys are rendered as they are so that a typo is noticeable.
+    //The placeholders are substituted in a single pass, so a value containing `{...}` (e.g. a nickname) is never substituted again.
+    pub fn render_in(&self, locale: Locale, key: &str, params: &[(&str, &str)]) -> String {
+        let template = [locale, self.locale, Locale::default()]
+            .iter()
+            .find_map(|l| self.messages.get(l).and_then(|m| m.get(key)));
+        let template = match template {
+            None => return key.to_string(),
+            Some(l) => l.choose(&mut rand::thread_rng()).unwrap(),
+        };
+        self.placeholder_regex
+            .replace_all(template, |caps: &Captures| {
+                match params.iter().find(|(name, _)| *name == &caps[1]) {
+                    Some((_, value)) => value.to_string(),
+                    None => caps[0].to_string(),
+                }
+            })
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let catalog = MessageCatalog::new(None, Locale::Ja).unwrap();
+        assert_eq!(
+            "Mikeさん、ハートありがとう。",
+            catalog.render("heart", &[("nickname", "Mike")])
+        );
+        assert_eq!(
+            "Mikeさん、粗品ありがとう。",
+            catalog.render("item", &[("nickname", "Mike"), ("item", "粗品")])
+        );
+        assert_eq!(
+            "{nickname}さん、ハートありがとう。",
+            catalog.render("heart", &[])
+        );
+        assert_eq!("no_such_key", catalog.render("no_such_key", &[]));
+        //not substituted again
+        assert_eq!(
+            "{item}さん、粗品ありがとう。",
+            catalog.render("item", &[("nickname", "{item}"), ("item", "粗品")])
+        );
+        assert_eq!(
+            "Thank you for the heart, Mike!",
+            catalog.render_in(Locale::En, "heart", &[("nickname", "Mike")])
+        );
+        assert_eq!(
+            "Mike님, 하트 감사합니다.",
+            catalog.render_in(Locale::Ko, "heart", &[("nickname", "Mike")])
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let path = std::env::temp_dir().join("spoon_comment_viewer_message_catalog_test02.json");
+        let path = path.to_str().unwrap();
+        fs::write(
+            path,
+            r#"{"en": {"heart": ["a {nickname}", "b {nickname}"], "only_en": ["c"]}}"#,
+        )
+        .unwrap();
+        let catalog = MessageCatalog::new(Some(path), Locale::En).unwrap();
+        for _ in 0..10 {
+            let s = catalog.render("heart", &[("nickname", "Mike")]);
+            assert!(s == "a Mike" || s == "b Mike");
+        }
+        //falls back to the default catalog
+        assert_eq!(
+            "Thank you for the spoon, Mike!",
+            catalog.render("spoon", &[("nickname", "Mike")])
+        );
+        //falls back to the configured locale
+        assert_eq!("c", catalog.render_in(Locale::Ko, "only_en", &[]));
+
+        fs::write(path, r#"{"en": {"heart": []}}"#).unwrap();
+        assert!(MessageCatalog::new(Some(path), Locale::En).is_err());
+        fs::write(path, r#"{"fr": {"heart": ["a"]}}"#).unwrap();
+        assert!(MessageCatalog::new(Some(path), Locale::En).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+}
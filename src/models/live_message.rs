@@ -6,7 +6,6 @@ use serde_json::Value;
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LiveMessage {
-    pub event: String,
     #[serde(rename = "type")]
     pub type_field: String,
     pub live_id: i64,
@@ -4,7 +4,6 @@ use serde_json::Value;
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LivePresentLike {
-    pub event: String,
     pub live_id: i64,
     pub data: Data,
     pub items: Vec<Value>,
@@ -5,7 +5,6 @@ use serde_derive::Serialize;
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LiveRank {
-    pub event: String,
     #[serde(rename = "type")]
     pub type_field: String,
     pub live_id: String,
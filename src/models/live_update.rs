@@ -6,7 +6,6 @@ use serde_json::Value;
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LiveUpdate {
-    pub event: String,
     pub live_id: i64,
     pub data: Data,
     pub trigger: String,
@@ -1,10 +1,11 @@
 mod live_join;
 mod live_like;
-mod live_message;
+pub mod live_message;
 mod live_present;
 mod live_present_like;
 mod live_rank;
 mod live_update;
+mod spoon_event;
 mod use_item;
 
 pub use live_join::LiveJoin;
@@ -14,4 +15,5 @@ pub use live_present::LivePresent;
 pub use live_present_like::LivePresentLike;
 pub use live_rank::LiveRank;
 pub use live_update::LiveUpdate;
+pub use spoon_event::SpoonEvent;
 pub use use_item::UseItem;
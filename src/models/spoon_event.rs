@@ -0,0 +1,97 @@
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::*;
+
+//A message received via the Spoon WebSocket, dispatched by its `"event"` field.
+//Messages which are not any of the known events are kept as they are in `Unknown`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SpoonEvent {
+    LiveJoin(LiveJoin),
+    LiveMessage(LiveMessage),         //comment
+    LiveLike(LiveLike),               //heart
+    UseItem(UseItem),                 //粗品
+    LivePresentLike(LivePresentLike), //buster
+    LivePresent(LivePresent),         //spoon
+    LiveUpdate(LiveUpdate),
+    LiveRank(LiveRank),
+    #[serde(untagged)]
+    Unknown(Value),
+}
+
+impl SpoonEvent {
+    pub fn event_type(&self) -> &str {
+        match self {
+            SpoonEvent::LiveJoin(_) => "live_join",
+            SpoonEvent::LiveMessage(_) => "live_message",
+            SpoonEvent::LiveLike(_) => "live_like",
+            SpoonEvent::UseItem(_) => "use_item",
+            SpoonEvent::LivePresentLike(_) => "live_present_like",
+            SpoonEvent::LivePresent(_) => "live_present",
+            SpoonEvent::LiveUpdate(_) => "live_update",
+            SpoonEvent::LiveRank(_) => "live_rank",
+            SpoonEvent::Unknown(v) => v.get("event").and_then(|e| e.as_str()).unwrap_or_default(),
+        }
+    }
+}
+
+impl std::str::FromStr for SpoonEvent {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let s = r#"{"event":"live_join","type":"live_rpt","live_id":1,"result":{"code":0,"detail":"success"},"appversion":"8.3.3","useragent":"Web"}"#;
+        let e: SpoonEvent = s.parse().unwrap();
+        assert_eq!("live_join", e.event_type());
+        match e {
+            SpoonEvent::LiveJoin(o) => assert_eq!("success", o.result.detail),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let s = r#"{"event":"live_unknown_event","live_id":1}"#;
+        let e: SpoonEvent = s.parse().unwrap();
+        assert_eq!("live_unknown_event", e.event_type());
+        assert_eq!(SpoonEvent::Unknown(serde_json::from_str(s).unwrap()), e);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        assert!("{".parse::<SpoonEvent>().is_err());
+        //A known event which doesn't match its model is not dropped but kept as it is.
+        let s = r#"{"event":"live_join"}"#;
+        assert_eq!(
+            SpoonEvent::Unknown(serde_json::from_str(s).unwrap()),
+            s.parse().unwrap()
+        );
+        assert_eq!(
+            "",
+            r#"{"live_id":1}"#.parse::<SpoonEvent>().unwrap().event_type()
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test04() {
+        let e = SpoonEvent::LiveMessage(LiveMessage::default());
+        let s = serde_json::to_string(&e).unwrap();
+        assert!(s.starts_with(r#"{"event":"live_message","#));
+        assert_eq!(1, s.matches(r#""event""#).count());
+        assert_eq!(e, s.parse().unwrap());
+    }
+}
@@ -0,0 +1,191 @@
+//notes and tags on listeners, managed via the message tunnel (e.g. `!note 123 likes cats`) or the modes of the binary
+
+use std::error::Error;
+
+use chrono::{DateTime, Local};
+use itertools::Itertools;
+
+use super::database::{Database, NoteEntity, TagEntity};
+
+//tags which change how the bot treats the listener
+pub const TAG_NO_GREETING: &str = "no_greeting";
+pub const TAG_GREETING: &str = "greeting"; //the value is used as the greeting, in which `{nickname}` is replaced
+pub const TAG_BLOCK: &str = "block";
+
+//the modes of the binary, which are also available via the message tunnel
+pub const COMMANDS: &[&str] = &["notes", "note", "unnote", "tag", "untag"];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Greeting {
+    Default,
+    Suppressed,
+    Custom(String),
+}
+
+fn find_tag<'a>(tags: &'a [TagEntity], tag: &str) -> Option<&'a TagEntity> {
+    tags.iter().find(|t| t.tag == tag)
+}
+
+pub fn greeting(tags: &[TagEntity], nickname: &str) -> Greeting {
+    if (find_tag(tags, TAG_NO_GREETING).is_some()) {
+        return Greeting::Suppressed;
+    }
+    match find_tag(tags, TAG_GREETING) {
+        Some(t) if (!t.value.is_empty()) => {
+            Greeting::Custom(t.value.replace("{nickname}", nickname))
+        }
+        _ => Greeting::Default,
+    }
+}
+
+pub fn should_block(tags: &[TagEntity]) -> bool {
+    find_tag(tags, TAG_BLOCK).is_some()
+}
+
+fn format_tag(tag: &TagEntity) -> String {
+    if (tag.value.is_empty()) {
+        format!("#{}", tag.tag)
+    } else {
+        format!("#{}={}", tag.tag, tag.value)
+    }
+}
+
+//a one-line summary printed beside the join line (`None` if there is nothing to show)
+pub fn summary(notes: &[NoteEntity], tags: &[TagEntity]) -> Option<String> {
+    let items = notes
+        .iter()
+        .map(|note| note.content.clone())
+        .chain(tags.iter().map(format_tag))
+        .collect::<Vec<_>>();
+    if (items.is_empty()) {
+        None
+    } else {
+        Some(items.join(" / "))
+    }
+}
+
+fn parse_id<T: std::str::FromStr>(s: &str) -> Result<T, Box<dyn Error>> {
+    s.parse()
+        .map_err(|_| format!("invalid id [ {} ]", s).into())
+}
+
+//executes a command such as `["note", "123", "likes", "cats"]`, returning the result to be shown
+pub fn execute(
+    database: &Database,
+    args: &[String],
+    now: DateTime<Local>,
+) -> Result<String, Box<dyn Error>> {
+    let args = args.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+    match args.as_slice() {
+        ["notes", listener_id] => {
+            let listener_id = parse_id(listener_id)?;
+            let notes = database.select_notes(listener_id)?;
+            let tags = database.select_tags(listener_id)?;
+            Ok(notes
+                .iter()
+                .map(|note| {
+                    format!(
+                        "[{}] {} ({})",
+                        note.id,
+                        note.content,
+                        note.created_at.format("%Y-%m-%d")
+                    )
+                })
+                .chain(tags.iter().map(format_tag))
+                .join("\n"))
+        }
+        ["note", listener_id, content @ ..] if (!content.is_empty()) => {
+            let listener_id = parse_id(listener_id)?;
+            let id = database.insert_note(listener_id, &content.join(" "), now)?;
+            Ok(format!(
+                "Added the note [{}] to the listener {}.",
+                id, listener_id
+            ))
+        }
+        ["unnote", id] => {
+            if (database.delete_note(parse_id(id)?)?) {
+                Ok(format!("Deleted the note [{}].", id))
+            } else {
+                Err(format!("note [{}] is not found", id).into())
+            }
+        }
+        ["tag", listener_id, tag, value @ ..] => {
+            let listener_id = parse_id(listener_id)?;
+            database.set_tag(listener_id, tag, &value.join(" "))?;
+            Ok(format!("Tagged the listener {} with #{}.", listener_id, tag))
+        }
+        ["untag", listener_id, tag] => {
+            let listener_id = parse_id(listener_id)?;
+            if (database.remove_tag(listener_id, tag)?) {
+                Ok(format!("Removed #{} from the listener {}.", tag, listener_id))
+            } else {
+                Err(format!("the listener {} does not have #{}", listener_id, tag).into())
+            }
+        }
+        _ => Err(format!(
+            "invalid arguments {:?} (expected `notes <listener id>`, `note <listener id> <content>`, `unnote <note id>`, `tag <listener id> <tag> [<value>]` or `untag <listener id> <tag>`)",
+            args
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let db = Database::new(None).unwrap();
+        let now = Local.with_ymd_and_hms(2023, 3, 14, 21, 0, 0).unwrap();
+        let run = |s: &str| {
+            execute(
+                &db,
+                &s.split_whitespace().map(String::from).collect::<Vec<_>>(),
+                now,
+            )
+        };
+
+        run("note 10 birthday 3/14").unwrap();
+        run("note 10 likes cats").unwrap();
+        run("tag 10 greeting Happy birthday, {nickname}!").unwrap();
+        assert_eq!(
+            "[1] birthday 3/14 (2023-03-14)\n[2] likes cats (2023-03-14)\n#greeting=Happy birthday, {nickname}!",
+            run("notes 10").unwrap()
+        );
+        run("unnote 1").unwrap();
+        assert!(run("unnote 1").is_err());
+        assert!(run("note x troll").is_err());
+        assert!(run("note 10").is_err());
+
+        let notes = db.select_notes(10).unwrap();
+        let tags = db.select_tags(10).unwrap();
+        assert_eq!(
+            Some("likes cats / #greeting=Happy birthday, {nickname}!".to_string()),
+            summary(&notes, &tags)
+        );
+        assert_eq!(None, summary(&[], &[]));
+        assert_eq!(
+            Greeting::Custom("Happy birthday, Mike!".to_string()),
+            greeting(&tags, "Mike")
+        );
+        assert!(!should_block(&tags));
+
+        run("tag 10 no_greeting").unwrap();
+        run("tag 10 block").unwrap();
+        let tags = db.select_tags(10).unwrap();
+        assert_eq!(Greeting::Suppressed, greeting(&tags, "Mike"));
+        assert!(should_block(&tags));
+
+        run("untag 10 no_greeting").unwrap();
+        run("untag 10 greeting").unwrap();
+        assert!(run("untag 10 greeting").is_err());
+        assert_eq!(
+            Greeting::Default,
+            greeting(&db.select_tags(10).unwrap(), "Mike")
+        );
+    }
+}
@@ -14,8 +14,25 @@ pub struct AudioEffect {
     pub fast: bool,
     pub slow: bool,
     pub repeat: bool,
+}
 
-    pub pitch_for_english: bool,
+//the pitch shifts in cents applied by `sox`, which depend on the voice
+//The `high` and `low` effects are relative to `base`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pitch {
+    pub base: i32,
+    pub high: i32,
+    pub low: i32,
+}
+
+impl Default for Pitch {
+    fn default() -> Self {
+        Self {
+            base: 0,
+            high: 300,
+            low: -250,
+        }
+    }
 }
 
 /*-------------------------------------*/
@@ -25,6 +42,7 @@ pub struct Audio {
     path: String,
     volume: f64,
     effect: AudioEffect,
+    pitch: Pitch,
 }
 
 impl Audio {
@@ -33,8 +51,13 @@ impl Audio {
             path: path.to_string(),
             volume,
             effect,
+            pitch: Pitch::default(),
         }
     }
+
+    pub fn with_pitch(self, pitch: Pitch) -> Self {
+        Self { pitch, ..self }
+    }
 }
 
 /*-------------------------------------*/
@@ -85,27 +108,21 @@ impl Player {
 
         //applies audio effects
         {
-            let mut set_args = |v: Vec<&'static str>| {
+            let mut set_args = |v: Vec<&str>| {
                 v.iter().for_each(|e| args.push(e.to_string()));
             };
             if (audio.effect.reverb) {
                 set_args(vec!["pad", "0", "2", "reverb"]);
             }
-            if (audio.effect.pitch_for_english) {
-                if (audio.effect.high) {
-                    set_args(vec!["pitch", "450"]);
-                } else if (audio.effect.low) {
-                    set_args(vec!["pitch", "-300"]);
-                } else {
-                    set_args(vec!["pitch", "150"]);
-                }
-            } else {
-                if (audio.effect.high) {
-                    set_args(vec!["pitch", "300"]);
-                }
-                if (audio.effect.low) {
-                    set_args(vec!["pitch", "-250"]);
-                }
+            let mut pitch = audio.pitch.base;
+            if (audio.effect.high) {
+                pitch += audio.pitch.high;
+            }
+            if (audio.effect.low) {
+                pitch += audio.pitch.low;
+            }
+            if (pitch != 0) {
+                set_args(vec!["pitch", &pitch.to_string()]);
             }
             if (audio.effect.left) {
                 set_args(vec!["remix", "1v1", "1v0"]);
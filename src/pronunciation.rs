@@ -0,0 +1,184 @@
+//the pronunciation dictionary applied to the text before the text-to-speech,
+// managed via the message tunnel (e.g. `!dict 草 くさ`) or the modes of the binary
+
+use std::error::Error;
+
+use chrono::{DateTime, Local};
+use itertools::Itertools;
+use log::error;
+use regex::{NoExpand, Regex};
+
+use super::database::{Database, PronunciationEntity};
+
+//the modes of the binary, which are also available via the message tunnel
+pub const COMMANDS: &[&str] = &["dicts", "dict", "dict_regex", "undict"];
+
+pub struct Dictionary {
+    entries: Vec<(Regex, PronunciationEntity)>,
+    url_regex: Regex,
+    emoji_regex: Regex,
+    laugh_regex: Regex,
+}
+
+//e.g. `ＡＢＣ１２３` to `ABC123`
+fn to_half_width(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '！'..='～' => char::from_u32(c as u32 - 0xFEE0).unwrap(),
+            '　' => ' ',
+            _ => c,
+        })
+        .collect()
+}
+
+fn compile(entity: &PronunciationEntity) -> Result<Regex, regex::Error> {
+    if (entity.is_regex) {
+        Regex::new(&entity.pattern)
+    } else {
+        Regex::new(&regex::escape(&entity.pattern))
+    }
+}
+
+impl Dictionary {
+    //The entries with an invalid regex are skipped.
+    pub fn new(entities: Vec<PronunciationEntity>) -> Self {
+        let entries = entities
+            .into_iter()
+            .filter_map(|entity| match compile(&entity) {
+                Ok(re) => Some((re, entity)),
+                Err(e) => {
+                    error!("Skipped the pronunciation [ {} ]: {}", entity.pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            entries,
+            url_regex: Regex::new(r#"https?://[^\s]+"#).unwrap(),
+            emoji_regex: Regex::new(
+                r#"[\p{Extended_Pictographic}\u{1F1E6}-\u{1F1FF}\u{1F3FB}-\u{1F3FF}\u{FE0F}\u{200D}]"#,
+            )
+            .unwrap(),
+            //`w` after an alphabet is a part of an English word (e.g. `awww`)
+            laugh_regex: Regex::new(r#"(^|[^A-Za-z])w{2,}"#).unwrap(),
+        }
+    }
+
+    //The URLs are replaced first so that the entries do not match a part of them,
+    // and the emojis are removed last so that the entries can give them a reading.
+    pub fn apply(&self, s: &str) -> String {
+        let mut ret = to_half_width(s);
+        ret = self.url_regex.replace_all(&ret, "URL").to_string();
+        for (re, entity) in &self.entries {
+            ret = if (entity.is_regex) {
+                re.replace_all(&ret, entity.reading.as_str()).to_string()
+            } else {
+                re.replace_all(&ret, NoExpand(&entity.reading)).to_string()
+            };
+        }
+        ret = self.laugh_regex.replace_all(&ret, "${1}笑").to_string();
+        ret = self.emoji_regex.replace_all(&ret, "").to_string();
+        ret.trim().to_string()
+    }
+}
+
+//executes a command such as `["dict", "草", "くさ"]`, returning the result to be shown
+pub fn execute(
+    database: &Database,
+    args: &[String],
+    now: DateTime<Local>,
+) -> Result<String, Box<dyn Error>> {
+    let args = args.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+    match args.as_slice() {
+        ["dicts"] => Ok(database
+            .select_pronunciations()?
+            .iter()
+            .map(|entity| {
+                format!(
+                    "{}{} -> {}",
+                    entity.pattern,
+                    if (entity.is_regex) { " (regex)" } else { "" },
+                    entity.reading
+                )
+            })
+            .join("\n")),
+        [mode @ ("dict" | "dict_regex"), pattern, reading @ ..] if (!reading.is_empty()) => {
+            let entity = PronunciationEntity {
+                pattern: pattern.to_string(),
+                reading: reading.join(" "),
+                is_regex: (*mode == "dict_regex"),
+            };
+            compile(&entity)?;
+            database.upsert_pronunciation(&entity, now)?;
+            Ok(format!(
+                "Registered the pronunciation [ {} -> {} ].",
+                entity.pattern, entity.reading
+            ))
+        }
+        ["undict", pattern] => {
+            if (database.delete_pronunciation(pattern)?) {
+                Ok(format!("Deleted the pronunciation of [ {} ].", pattern))
+            } else {
+                Err(format!("pronunciation of [ {} ] is not found", pattern).into())
+            }
+        }
+        _ => Err(format!(
+            "invalid arguments {:?} (expected `dicts`, `dict <word> <reading>`, `dict_regex <regex> <reading>` or `undict <word or regex>`)",
+            args
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let dictionary = Dictionary::new(vec![]);
+        assert_eq!("こんにちは", dictionary.apply("こんにちは🌙✨"));
+        assert_eq!("ありがとう", dictionary.apply("👍🏻ありがとう❤️"));
+        assert_eq!("おもしろい笑", dictionary.apply("おもしろいwwwww"));
+        assert_eq!("おもしろい笑", dictionary.apply("おもしろいｗｗｗ"));
+        assert_eq!("awww", dictionary.apply("awww"));
+        assert_eq!("Mike123", dictionary.apply("Ｍｉｋｅ１２３"));
+        assert_eq!(
+            "見てね URL",
+            dictionary.apply("見てね https://www.spooncast.net/jp/live/123")
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let db = Database::new(None).unwrap();
+        let now = Local::now();
+        let run = |s: &str| {
+            execute(
+                &db,
+                &s.split_whitespace().map(String::from).collect::<Vec<_>>(),
+                now,
+            )
+        };
+
+        run("dict 草 くさ").unwrap();
+        run("dict_regex ^(.+)ちゃそ$ ${1}ちゃん").unwrap();
+        run("dict 🐱 ねこ").unwrap();
+        assert!(run("dict_regex ( かっこ").is_err());
+        assert!(run("dict 草").is_err());
+        assert_eq!(
+            "草 -> くさ\n^(.+)ちゃそ$ (regex) -> ${1}ちゃん\n🐱 -> ねこ",
+            run("dicts").unwrap()
+        );
+
+        let dictionary = Dictionary::new(db.select_pronunciations().unwrap());
+        assert_eq!("くさ生える", dictionary.apply("草生える"));
+        assert_eq!("ももちゃん", dictionary.apply("ももちゃそ"));
+        assert_eq!("ねこ好き", dictionary.apply("🐱好き✨"));
+
+        run("undict 草").unwrap();
+        assert!(run("undict 草").is_err());
+    }
+}
@@ -8,6 +8,8 @@ pub struct Selenium {
     driver: WebDriver,
 }
 
+//`WebDriverError` is defined by `thirtyfour_sync`, so its size is out of our control.
+#[allow(clippy::result_large_err)]
 impl Selenium {
     pub fn new(
         webdriver_port: usize,
@@ -62,11 +64,11 @@ impl Selenium {
         self.driver.get(url)
     }
 
-    pub fn query(&self, css_selector: &str) -> Result<WebElement, WebDriverError> {
+    pub fn query(&self, css_selector: &str) -> Result<WebElement<'_>, WebDriverError> {
         self.driver.find_element(By::Css(css_selector))
     }
 
-    pub fn query_all(&self, css_selector: &str) -> Result<Vec<WebElement>, WebDriverError> {
+    pub fn query_all(&self, css_selector: &str) -> Result<Vec<WebElement<'_>>, WebDriverError> {
         self.driver.find_elements(By::Css(css_selector))
     }
 
@@ -11,41 +11,48 @@ use std::thread;
 use std::time::Duration;
 use std::time::Instant;
 
+use chrono::Local;
 use itertools::Itertools;
 use log::error;
 use log::info;
-use rand::prelude::SliceRandom;
 use rand::rngs::ThreadRng;
-use rand::seq::IteratorRandom;
-use rand::Rng;
-use regex::Regex;
-use serde_json::Map;
-use serde_json::Value;
-use thirtyfour_sync::error::WebDriverError;
 
+use super::archive;
+use super::backend::SpoonBackend;
 use super::bgm::BGM;
 use super::chatgpt::ChatGPT;
+use super::command::{self, modifier, CommandContext, CommandRegistry, Permission};
+use super::comment_queue::{CommentQueue, Priority};
 use super::config::Config;
 use super::constant;
-use super::database::{Database, ListenerEntity};
+use super::database::{
+    self, Database, DatabaseError, GiftEntity, GiftKind, ListenerEntity, Milestone, PendingWrite,
+    WriteBuffer,
+};
 use super::filter::Filter;
 use super::listener::Listener;
+use super::locale::Locale;
 use super::logger::Logger;
+use super::loyalty;
+use super::message_catalog::MessageCatalog;
+use super::models::live_message::User;
 use super::models::*;
+use super::notes::{self, Greeting};
 use super::player::Audio;
 use super::player::AudioEffect;
+use super::pronunciation::{self, Dictionary};
 use super::selenium::Selenium;
 use super::spoon_core::Spoon;
-use super::util;
 use super::voicevox::Script;
 use super::voicevox::VoiceVox;
-use super::websocket::WebSocket;
+use super::websocket::{ConnectionEvent, WebSocket};
 
 pub struct SpoonClient {
-    spoon: Spoon,
+    spoon: Rc<dyn SpoonBackend>,
     websocket: WebSocket,
 
     config: Rc<Config>,
+    messages: MessageCatalog,
 
     logger: Logger,
 
@@ -55,42 +62,63 @@ pub struct SpoonClient {
     guide_flags: Vec<bool>,
 
     database: Database,
+    pending_writes: WriteBuffer, //retried when the database is temporarily unavailable
 
     chatgpt: ChatGPT,
     voicevox: VoiceVox,
     bgm: BGM,
+    commands: Rc<CommandRegistry>,
 
-    z: Rc<Selenium>,
+    access_token: Option<String>, //for posting comments via WebSocket
+    comment_queue: CommentQueue,
 
     //listeners
     previous_listeners_set: HashSet<Listener>, //for `いらっしゃい`, `おかえりなさい`, `またきてね`
     previous_listeners_map: HashMap<Listener, Instant>, //for `xxx秒の滞在でした`
     cumulative_listeners: HashSet<Listener>,   //for `おかえりなさい`
+    listener_locales: HashMap<usize, Locale>,  //guessed from the `country` field of the events
 }
 
 impl SpoonClient {
-    pub fn new(config: Rc<Config>) -> Self {
-        let filter = Filter::new(&config.forbidden_words);
-
-        let database = Database::new(Some(&config.database_file));
-
-        let chatgpt = ChatGPT::new(&config, filter.clone());
-        let voicevox = VoiceVox::new(&config, filter);
-        let bgm = BGM::new();
-
+    pub fn new(config: Rc<Config>) -> Result<Self, Box<dyn Error>> {
         let z = Rc::new(Selenium::new(
             config.selenium.webdriver_port,
             Duration::from_millis(config.selenium.implicit_timeout_ms),
             config.selenium.should_maximize_window,
         ));
+        let spoon = Rc::new(Spoon::new(z, Duration::from_millis(3000)));
+        Self::with_backend(config, spoon)
+    }
+
+    pub fn with_backend(
+        config: Rc<Config>,
+        spoon: Rc<dyn SpoonBackend>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let filter = Filter::new(&config.forbidden_words);
 
-        Self {
-            spoon: Spoon::new(z.clone(), Duration::from_millis(3000)),
+        let database = Database::new(Some(&config.database_file))
+            .map_err(|e| format!("failed to open [ {} ]: {}", config.database_file, e))?;
+        //The bot should not stall while the database is locked; the writes are retried later.
+        database.set_busy_timeout(database::LIVE_BUSY_TIMEOUT)?;
+
+        let chatgpt = ChatGPT::new(&config, filter.clone());
+        let voicevox = VoiceVox::new(&config, filter, Self::load_dictionary(&database));
+        let bgm = BGM::new();
+        let comment_queue =
+            CommentQueue::new(Duration::from_millis(config.spoon.min_comment_interval_ms));
+        let messages = MessageCatalog::new(
+            Some(&config.spoon.message_catalog_file),
+            config.spoon.locale,
+        )?;
+
+        Ok(Self {
+            spoon: spoon.clone(),
             websocket: WebSocket::new(),
 
             config,
+            messages,
 
-            logger: Logger::new(z.clone()),
+            logger: Logger::new(spoon),
 
             rng: rand::thread_rng(),
 
@@ -98,15 +126,20 @@ impl SpoonClient {
             guide_flags: vec![false; 3],
 
             database,
+            pending_writes: WriteBuffer::new(),
             chatgpt,
             voicevox,
             bgm,
-            z,
+            commands: Rc::new(CommandRegistry::with_default_commands()),
+
+            access_token: None,
+            comment_queue,
 
             previous_listeners_set: HashSet::new(),
             previous_listeners_map: HashMap::new(),
             cumulative_listeners: HashSet::new(),
-        }
+            listener_locales: HashMap::new(),
+        })
     }
 
     pub fn login(
@@ -114,7 +147,7 @@ impl SpoonClient {
         url: &str,
         twitter_id: &str,
         twitter_password: &str,
-    ) -> Result<(), WebDriverError> {
+    ) -> Result<(), Box<dyn Error>> {
         self.spoon.login(url, twitter_id, twitter_password)
     }
 
@@ -166,16 +199,137 @@ impl SpoonClient {
     }
 
     pub fn init(&mut self) -> Result<(), Box<dyn Error>> {
+        let c = &self.config.spoon.websocket;
+        if (!c.capture_file.is_empty()) {
+            self.websocket.record(&c.capture_file)?;
+        }
         let live_id = self.spoon.update_live_id()?;
-        self.websocket.connect(live_id)?;
+        if let Err(e) = self.database.insert_live(live_id, Local::now()) {
+            error!("{}", e);
+        }
+        match self.database.end_stale_visits(live_id) {
+            Ok(0) => (),
+            Ok(n) => info!("Ended {} visits left open in the previous lives.", n),
+            Err(e) => error!("{}", e),
+        }
+        if (self.config.spoon.should_post_via_websocket) {
+            match self.spoon.access_token() {
+                Ok(s) => self.access_token = Some(s),
+                Err(e) => error!("Comments are posted via the browser: {}", e),
+            }
+        }
+        self.websocket.connect(
+            live_id,
+            Duration::from_millis(c.initial_backoff_ms),
+            Duration::from_millis(c.max_backoff_ms),
+            if (c.heartbeat_interval_ms == 0) {
+                None
+            } else {
+                Some(Duration::from_millis(c.heartbeat_interval_ms))
+            },
+        )?;
         self.elapsed = Instant::now();
         Ok(())
     }
 
+    //processes the frames in the capture file `capture_file` instead of connecting to Spoon, returning when all of them have been processed
+    //This is meant to be used with `FakeBackend`, as the comments of the bot are posted via `self.spoon`.
+    pub fn replay(&mut self, capture_file: &str) -> Result<(), Box<dyn Error>> {
+        info!("Replaying [ {} ]...", capture_file);
+        self.websocket
+            .replay(capture_file, self.config.spoon.websocket.replay_speed)?;
+        self.elapsed = Instant::now();
+        loop {
+            let is_fed = !self.websocket.is_replaying();
+            self.process_comments()?;
+            if (is_fed && self.comment_queue.is_empty()) {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(
+                self.config.spoon.comment_check_interval_ms,
+            ));
+        }
+    }
+
+    //the pronunciation dictionary registered in the database (empty while the database is unavailable)
+    fn load_dictionary(database: &Database) -> Dictionary {
+        Dictionary::new(database.select_pronunciations().unwrap_or_else(|e| {
+            error!("{}", e);
+            vec![]
+        }))
+    }
+
+    //posts a comment via WebSocket if enabled, falling back to the browser
+    fn post_comment(&self, s: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(token) = &self.access_token {
+            match self.websocket.post_comment(token, s) {
+                Ok(()) => return Ok(()),
+                Err(e) => error!("Failed to post a comment via WebSocket: {}", e),
+            }
+        }
+        self.spoon.post_comment(s)
+    }
+
+    fn queue_comment(&mut self, s: &str, priority: Priority) {
+        self.comment_queue.push(s, priority);
+    }
+
+    //queues the message `key` naming `nickname`, which is merged with the waiting ones naming other listeners
+    //`params` should not contain `nickname`.
+    fn queue_grouped_comment(
+        &mut self,
+        locale: Locale,
+        key: &str,
+        params: &[(&str, &str)],
+        nickname: &str,
+        priority: Priority,
+    ) {
+        let group = format!("{}/{}/{:?}", locale.code(), key, params);
+        let template = self.messages.render_in(locale, key, params);
+        let separator = self.messages.render_in(locale, "nickname_separator", &[]);
+        self.comment_queue
+            .push_grouped(&group, &template, &separator, nickname, priority);
+    }
+
+    //posts the queued comments which are due
+    fn flush_comments(&mut self) {
+        while let Some(s) = self.comment_queue.pop() {
+            self.post_comment(&s).unwrap_or_else(|e| error!("{}", e));
+        }
+    }
+
+    //follows `msg_interval` of the live, which is the minimum interval between comments allowed by Spoon
+    fn update_comment_interval(&mut self, msg_interval: i64) {
+        let interval = Duration::from_millis(msg_interval.max(0) as u64).max(
+            Duration::from_millis(self.config.spoon.min_comment_interval_ms),
+        );
+        self.comment_queue.set_interval(interval);
+    }
+
+    //the locale of the messages addressed to the user `user_id`
+    fn listener_locale(&self, user_id: usize) -> Locale {
+        if (!self.config.spoon.should_use_listener_locale) {
+            return self.messages.locale();
+        }
+        *self
+            .listener_locales
+            .get(&user_id)
+            .unwrap_or(&self.messages.locale())
+    }
+
+    //remembers the country of the user `user_id` and then returns `listener_locale()`
+    fn update_listener_locale(&mut self, user_id: i64, country: &str) -> Locale {
+        if let Some(locale) = Locale::from_country(country) {
+            self.listener_locales.insert(user_id as usize, locale);
+        }
+        self.listener_locale(user_id as usize)
+    }
+
     fn process_message_comment(&mut self, o: LiveMessage) -> Result<(), Box<dyn Error>> {
         let text = &o.update_component.message.value;
         let user = &o.data.user.nickname;
         let id = &o.data.user.id;
+        let locale = self.update_listener_locale(*id, &o.data.user.country);
 
         self.logger.log(
             None,
@@ -187,221 +341,126 @@ impl SpoonClient {
                 text,
             ),
         )?;
+        if let Err(e) =
+            self.database
+                .insert_comment(*id as usize, self.spoon.live_id(), text, Local::now())
+        {
+            error!("{}", e);
+        }
 
         if (*id as usize == self.config.chatgpt.excluded_user_id) {
             return Ok(());
         }
 
-        let mut comment_text = text.to_string();
-        let mut effect = AudioEffect::default();
-        let mut speaker = self.config.voicevox.speaker;
-
-        let mut tokens = comment_text.split_whitespace().collect_vec();
+        let mut tokens = text.split_whitespace().collect_vec();
         if (tokens.is_empty()) {
             //This happened once.
             return Err("empty comment is unexpectedly detected".into());
         }
-        if (tokens[0] == "/bgm") {
-            if (self.config.spoon.live.bgm.audio_list.len() <= 1) {
-                let s = "BGMの再生に失敗しました。";
-                self.spoon.post_comment(s)?;
-                if (self.config.voicevox.enabled) {
-                    self.voicevox
-                        .say(Script::new(s, AudioEffect::default(), speaker));
-                }
+
+        if (tokens[0] == "help") {
+            let s = self.messages.render_in(locale, "help_without_slash", &[]);
+            self.queue_comment(&s, Priority::Normal);
+            return Ok(());
+        }
+        if let Some(name) = tokens[0].strip_prefix('/') {
+            if (self.execute_command(&o.data.user, locale, name, &tokens[1..])?) {
                 return Ok(());
             }
-            let audio_list = &self.config.spoon.live.bgm.audio_list[1..];
-            let bgm = audio_list.choose(&mut self.rng).unwrap();
-            let audio = Audio::new(&bgm.path, bgm.volume, AudioEffect::default());
-            self.bgm.push(&audio);
-            let s = format!("再生予定のBGMリストに [ {} ] を追加しました。", bgm.title);
-            self.spoon.post_comment(&s)?;
-            if (self.config.voicevox.enabled) {
-                self.voicevox.say(Script::new(
-                    "再生予定のBGMリストに楽曲を追加しました",
-                    AudioEffect::default(),
-                    speaker,
-                ));
-            }
-        } else if (self.config.chatgpt.enabled) {
-            if (tokens[0] == "help") {
-                let s = "help ではなくスラッシュを先頭に付けて\n/help と打ってみてね。";
-                self.spoon.post_comment(s)?;
-                return Ok(());
-            } else if (tokens[0] == "/help") {
-                let s = "[💡ヘルプ]\necho, asmr, zundamon のどれかを\n「/echo　こんにちは」\nのように使ってみてね。\n\n「/bgm」でBGMを変更できるよ。";
-                self.spoon.post_comment(s)?;
-                return Ok(());
-            } else if (tokens[0] == "/fortune") {
-                let fortune_names = vec![
-                    "総合運",
-                    "恋愛運",
-                    "金運",
-                    "ラッキーナンバー",
-                    "ラッキーカラー",
-                ];
-                let colors = vec![
-                    "藍", "青", "青緑", "青紫", "赤", "茜", "小豆", "黄", "黄緑", "金", "銀", "銅",
-                    "栗", "黒", "焦茶", "小麦", "紺", "桜", "珊瑚", "漆黒", "朱", "白", "空", "橙",
-                    "玉虫", "茶", "灰", "肌", "薔薇", "深緑", "水", "緑", "紫", "桃", "瑠璃",
-                    "透明",
-                ];
-
-                let mut l = (0..3)
-                    .map(|_| "★".repeat(self.rng.gen_range(1..=5)))
-                    .collect_vec();
-                l.push(self.rng.gen_range(0..=1000).to_string());
-                l.push(colors.iter().choose(&mut self.rng).unwrap().to_string());
-
-                let s = format!(
-                    "🔮 {}さん\n{}",
-                    user,
-                    fortune_names
-                        .iter()
-                        .zip(l)
-                        .map(|(name, value)| format!("{}: {}", name, value))
-                        .join("\n")
-                );
-                self.spoon.post_comment(&s)?;
-                return Ok(());
-            } else if (tokens[0] == "/rank") {
-                let all_entities = self
-                    .database
-                    .select_all()
-                    .into_iter()
-                    .sorted_by_key(|e| (e.stay_duration, e.visit_count))
-                    .rev()
-                    .collect_vec();
-
-                let index = all_entities
-                    .iter()
-                    .position(|entity| entity.id == *id as usize)
-                    .unwrap();
-
-                let elapsed = self
-                    .previous_listeners_map
-                    .iter()
-                    .find(|(k, _)| k.id == *id as usize)
-                    .unwrap()
-                    .1
-                    .elapsed();
-
-                let s = format!(
-                    "👑 {}さん\nランキング: {}位/{}人中\n滞在時間: {}\n訪問回数: {}回",
-                    user,
-                    index + 1,
-                    all_entities.len(),
-                    util::pretty_print_duration(all_entities[index].stay_duration + elapsed),
-                    all_entities[index].visit_count,
-                );
-                self.spoon.post_comment(&s)?;
-                return Ok(());
-            } else if (tokens[0] == "/ranking") {
-                let ranker = self
-                    .database
-                    .select_all()
-                    .into_iter()
-                    .sorted_by_key(|e| (e.stay_duration, e.visit_count))
-                    .rev()
-                    .take(5)
-                    .collect_vec();
-
-                let re = Regex::new(r#"\d+秒"#).unwrap();
-
-                let s = format!(
-                    "👑 ランキング\n{}",
-                    ranker
-                        .into_iter()
-                        .enumerate()
-                        .map(|(i, e)| format!(
-                            "{}. {}({}回)",
-                            i + 1,
-                            re.replace(&util::pretty_print_duration(e.stay_duration), ""),
-                            e.visit_count
-                        ))
-                        .join("\n")
-                );
-                self.spoon.post_comment(&s)?;
-                return Ok(());
-            } else if (tokens[0].starts_with('/')) {
-                let mut num_command = 0;
-                for token in &tokens {
-                    if (!token.starts_with('/')) {
-                        break;
-                    }
-                    num_command += 1;
-                    match *token {
-                        "/reverb" => effect.reverb = true,
-                        "/echo" => effect.reverb = true, //same as `/reverb`
-                        "/high" => effect.high = true,
-                        "/low" => effect.low = true,
-                        "/left" => effect.left = true, //low quality on Linux
-                        "/right" => effect.right = true, //low quality on Linux
-                        "/fast" => effect.fast = true,
-                        "/slow" => effect.slow = true,
-
-                        "/zundamon" => speaker = 3,
-                        "/zundamon_2" => speaker = 1,
-                        "/zundamon_3" => speaker = 7,
-                        "/zundamon_4" => speaker = 5,
-                        "/zundamon_5" => speaker = 38,
-
-                        "/asmr" => speaker = 22,
-
-                        "/sayo" => speaker = 46,
-
-                        "/tsumugi" => speaker = 8,
-
-                        "/himari" => speaker = 14,
-
-                        "/nurse" => speaker = 47,
-                        "/nurse_asmr" => speaker = 50,
-
-                        "/bii" => speaker = 58,
-                        "/bii_calm" => speaker = 59,
-                        "/bii_shy" => speaker = 60,
-
-                        _ => {
-                            let s = if (token.is_ascii()) {
-                                format!("`{}`は無効なコマンドだよ。`/help`で確認してね。", token,)
-                            } else {
-                                format!(
-                                                    "`{}`は無効なコマンドだよ。「/echo　こんにちは」というように、あいだにスペースが入っているか確認してみてね。",
-                                                    token,
-                                                )
-                            };
-                            self.spoon.post_comment(&s)?;
-                            return Ok(());
-                        }
-                    }
-                }
-                if (tokens.len() == num_command) {
-                    let command = tokens.iter().take(num_command).join(" ");
-                    let s = format!(
-                        "`{}`単体では使用できないよ。「{}　こんにちは」のように、テキストを足してみてね。",
-                        command,
-                        command,
-                    );
-                    self.spoon.post_comment(&s)?;
-                    return Ok(());
-                }
-                for _ in 0..num_command {
-                    tokens.remove(0);
+        }
+
+        if (!self.config.chatgpt.enabled) {
+            return Ok(());
+        }
+
+        let num_modifier = tokens.iter().take_while(|t| t.starts_with('/')).count();
+        let mut effect = AudioEffect::default();
+        let (preset, invalid_token) = {
+            let voicevox = &self.config.voicevox;
+            let mut preset = command::preset_of_listener(voicevox, &self.database, *id as usize);
+            let invalid_token = tokens[..num_modifier]
+                .iter()
+                .find(|token| !modifier::apply(token, &voicevox.voices, &mut effect, &mut preset));
+            (preset.cloned(), invalid_token.cloned())
+        };
+        if let Some(token) = invalid_token {
+            let key = if (token.is_ascii()) {
+                "invalid_command"
+            } else {
+                "invalid_command_non_ascii"
+            };
+            let s = self.messages.render_in(locale, key, &[("command", token)]);
+            self.queue_comment(&s, Priority::Normal);
+            return Ok(());
+        }
+        if (tokens.len() == num_modifier) {
+            let command = tokens.join(" ");
+            let s =
+                self.messages
+                    .render_in(locale, "command_without_text", &[("command", &command)]);
+            self.queue_comment(&s, Priority::Normal);
+            return Ok(());
+        }
+        tokens.drain(..num_modifier);
+
+        let mut script = Script::new(&tokens.join(" "), effect, self.config.voicevox.speaker);
+        if let Some(preset) = preset {
+            for name in &preset.effects {
+                if (!modifier::apply_effect(name, &mut script.effect)) {
+                    error!("Unknown effect `{}` in the voice `{}`.", name, preset.name);
                 }
-                comment_text = tokens.join(" ");
             }
-
-            self.chatgpt.push(Script::new(
-                &comment_text.split_whitespace().join(" "),
-                effect,
-                speaker,
-            ));
+            script = script.with_preset(&preset);
         }
+        self.chatgpt.push(script);
 
         Ok(())
     }
 
+    //executes the chat command `/{name}`
+    //This returns `false` if no such command is registered.
+    fn execute_command(
+        &mut self,
+        user: &User,
+        locale: Locale,
+        name: &str,
+        args: &[&str],
+    ) -> Result<bool, Box<dyn Error>> {
+        let registry = self.commands.clone();
+        let command = match registry.find(name) {
+            None => return Ok(false),
+            Some(c) => c,
+        };
+        if (Permission::of(user) < command.permission()) {
+            let s = self.messages.render_in(
+                locale,
+                "permission_denied",
+                &[("command", &format!("/{}", name))],
+            );
+            self.queue_comment(&s, Priority::Normal);
+            return Ok(true);
+        }
+
+        let mut ctx = CommandContext {
+            config: &self.config,
+            messages: &self.messages,
+            locale,
+            registry: &registry,
+            database: &self.database,
+            voicevox: &mut self.voicevox,
+            bgm: &self.bgm,
+            rng: &mut self.rng,
+            listeners: &self.previous_listeners_map,
+            user,
+            replies: vec![],
+        };
+        let result = command.execute(&mut ctx, args);
+        for s in ctx.replies {
+            self.queue_comment(&s, Priority::Normal);
+        }
+        result.map(|_| true)
+    }
+
     fn process_guide(&mut self) -> Result<(), Box<dyn Error>> {
         let elapsed = self.elapsed.elapsed();
 
@@ -409,44 +468,66 @@ impl SpoonClient {
         let guide_5 = Duration::from_secs(3600 * 2 - 5 * 60);
         let guide_1 = Duration::from_secs(3600 * 2 - 60);
 
-        let message = if ((elapsed > guide_10) && !self.guide_flags[0]) {
+        let minutes = if ((elapsed > guide_10) && !self.guide_flags[0]) {
             self.guide_flags[0] = true;
-            "配信終了10分前だよ"
+            10
         } else if ((elapsed > guide_5) && !self.guide_flags[1]) {
             self.guide_flags[1] = true;
-            "配信終了5分前だよ"
+            5
         } else if ((elapsed > guide_1) && !self.guide_flags[2]) {
             self.guide_flags[2] = true;
-            "配信終了1分前だよ"
+            1
         } else {
             return Ok(());
         };
+        let message = self
+            .messages
+            .render("guide", &[("minutes", &minutes.to_string())]);
 
-        self.logger.log(Some(constant::COLOR_WHITE), message)?;
+        self.logger.log(Some(constant::COLOR_WHITE), &message)?;
         if (self.config.spoon.should_comment_guide) {
-            self.spoon.post_comment(message)?;
+            self.queue_comment(&message, Priority::High);
             if (self.config.voicevox.enabled) {
                 self.voicevox.say(Script::new(
-                    message,
+                    &message,
                     AudioEffect::default(),
                     self.config.voicevox.speaker,
                 ));
             }
         }
 
-        if (message.contains("1分前") && self.config.spoon.should_call_over) {
+        if ((minutes == 1) && self.config.spoon.should_call_over) {
             self.call_over()?;
         }
 
         Ok(())
     }
 
+    fn record_gift(&self, listener_id: i64, kind: GiftKind, item: &str, amount: i64, combo: i64) {
+        let entity = GiftEntity::new(
+            listener_id as usize,
+            self.spoon.live_id(),
+            kind,
+            item,
+            amount,
+            combo,
+            Local::now(),
+        );
+        if let Err(e) = self.database.insert_gift(&entity) {
+            error!("{}", e);
+        }
+    }
+
     fn process_like_comment(&mut self, o: LiveLike) -> Result<(), Box<dyn Error>> {
         let user = o.data.author.nickname;
-        let c = format!("{}さん、ハートありがとう。", user);
+        let locale = self.update_listener_locale(o.data.author.id, &o.data.author.country);
+        let c = self
+            .messages
+            .render_in(locale, "heart", &[("nickname", &user)]);
         self.logger.log(Some(constant::COLOR_YELLOW), &c)?;
+        self.record_gift(o.data.author.id, GiftKind::Heart, "", 1, 1);
         if (self.config.spoon.should_comment_heart) {
-            self.spoon.post_comment(&c)?;
+            self.queue_grouped_comment(locale, "heart", &[], &user, Priority::Low);
             if (self.config.voicevox.enabled) {
                 self.voicevox.say(Script::new(
                     &c,
@@ -463,20 +544,29 @@ impl SpoonClient {
 
     fn process_use_item_comment(&mut self, o: UseItem) -> Result<(), Box<dyn Error>> {
         let user = o.data.user.nickname;
-        let (item_id, effect, amount) = match o.use_items.get(0) {
+        let locale = self.update_listener_locale(o.data.user.id, &o.data.user.country);
+        let (item_id, effect, amount) = match o.use_items.first() {
             None => return Err("`use_items` is empty".into()),
             Some(l) => (l.item_id, &l.effect, l.amount),
         };
-
-        let item_name = if (item_id == 34) {
-            "粗品"
+        self.record_gift(
+            o.data.user.id,
+            GiftKind::Item,
+            &item_id.to_string(),
+            amount,
+            1,
+        );
+
+        let item_key = if (item_id == 34) {
+            "item_sosina"
         } else if (effect == "LIKE") {
-            "バスター"
+            "item_buster"
         } else if (effect == "DICE_LIKE") {
-            "サイコロ"
+            "item_dice"
         } else {
-            "謎のアイテム"
+            "item_unknown"
         };
+        let item_name = self.messages.render(item_key, &[]);
 
         self.logger.log(
             None,
@@ -491,8 +581,11 @@ impl SpoonClient {
         )?;
 
         if (self.config.spoon.should_comment_spoon) {
-            let s = format!("{}さん、{}ありがとう。", user, item_name);
-            self.spoon.post_comment(&s)?;
+            let item = self.messages.render_in(locale, item_key, &[]);
+            let s =
+                self.messages
+                    .render_in(locale, "item", &[("nickname", &user), ("item", &item)]);
+            self.queue_grouped_comment(locale, "item", &[("item", &item)], &user, Priority::Low);
             if (self.config.voicevox.enabled) {
                 self.voicevox.say(Script::new(
                     &s,
@@ -510,7 +603,15 @@ impl SpoonClient {
 
     fn process_present_like_comment(&mut self, o: LivePresentLike) -> Result<(), Box<dyn Error>> {
         let user = o.data.user.nickname;
+        let locale = self.update_listener_locale(o.data.user.id, &o.data.user.country);
         let amount = o.update_component.like.amount * o.update_component.like.combo;
+        self.record_gift(
+            o.data.user.id,
+            GiftKind::Buster,
+            "",
+            o.update_component.like.amount,
+            o.update_component.like.combo,
+        );
 
         self.logger.log(
             None,
@@ -524,8 +625,10 @@ impl SpoonClient {
         )?;
 
         if (self.config.spoon.should_comment_spoon) {
-            let s = format!("{}さん、バスターありがとう。", user);
-            self.spoon.post_comment(&s)?;
+            let s = self
+                .messages
+                .render_in(locale, "buster", &[("nickname", &user)]);
+            self.queue_grouped_comment(locale, "buster", &[], &user, Priority::Low);
             if (self.config.voicevox.enabled) {
                 self.voicevox.say(Script::new(
                     &s,
@@ -543,7 +646,19 @@ impl SpoonClient {
 
     fn process_present_comment(&mut self, o: LivePresent) -> Result<(), Box<dyn Error>> {
         let user = o.data.author.nickname;
+        let locale = self.update_listener_locale(o.data.author.id, &o.data.author.country);
         let amount = o.data.amount * o.data.combo;
+        self.record_gift(
+            o.data.author.id,
+            GiftKind::Spoon,
+            "",
+            o.data.amount,
+            o.data.combo,
+        );
+        let loyalty = &self.config.spoon.loyalty;
+        if (loyalty.enabled && loyalty.should_celebrate_first_spoon) {
+            self.celebrate_first_spoon(locale, &user, o.data.author.id as usize, amount);
+        }
 
         self.logger.log(
             None,
@@ -557,8 +672,10 @@ impl SpoonClient {
         )?;
 
         if (self.config.spoon.should_comment_spoon) {
-            let s = format!("{}さん、スプーンありがとう。", user);
-            self.spoon.post_comment(&s)?;
+            let s = self
+                .messages
+                .render_in(locale, "spoon", &[("nickname", &user)]);
+            self.queue_grouped_comment(locale, "spoon", &[], &user, Priority::Low);
             if (self.config.voicevox.enabled) {
                 self.voicevox.say(Script::new(
                     &s,
@@ -576,18 +693,22 @@ impl SpoonClient {
 
     //点呼
     fn call_over(&mut self) -> Result<(), Box<dyn Error>> {
-        let c = "点呼するよ。";
-        self.spoon.post_comment(c)?;
+        let c = self.messages.render("call_over", &[]);
+        self.queue_comment(&c, Priority::Normal);
         if (self.config.voicevox.enabled) {
             self.voicevox.say(Script::new(
-                c,
+                &c,
                 AudioEffect::default(),
                 self.config.voicevox.speaker,
             ));
         }
-        for listener in &self.previous_listeners_set {
-            let c = format!("{}さん、来てくれてありがとう。", listener.nickname);
-            self.spoon.post_comment(&c)?;
+        for listener in self.previous_listeners_set.clone() {
+            let c = self.messages.render_in(
+                self.listener_locale(listener.id),
+                "call_over_listener",
+                &[("nickname", &listener.nickname)],
+            );
+            self.queue_comment(&c, Priority::Normal);
             if (self.config.voicevox.enabled) {
                 self.voicevox.say(Script::new(
                     &c,
@@ -595,44 +716,56 @@ impl SpoonClient {
                     self.config.voicevox.speaker,
                 ));
             }
-            std::thread::sleep(std::time::Duration::from_millis(50));
         }
         Ok(())
     }
 
+    //posts and reads aloud the replies of ChatGPT, each in the voice of the comment replied to
+    fn process_chatgpt_replies(&mut self) {
+        if (!self.config.chatgpt.enabled) {
+            return;
+        }
+        for mut e in self.chatgpt.fetch() {
+            let s = e.script.trim().to_string();
+            if (s == "QUOTA_ERROR") {
+                let _ = Command::new("curl")
+                    .args([
+                        &self.config.chatgpt.discord_url,
+                        "-d",
+                        r#"{"wait": true, "content": "OpenAI API quota exceeded"}"#,
+                        "-H",
+                        "Content-Type: application/json",
+                    ])
+                    .status();
+                let s = "AI部分にエラーが発生しました。管理人に通知を送信しました。一分後、枠を終了します。申し訳ございません。";
+                let _ = self.post_comment(s);
+                if (self.config.voicevox.enabled) {
+                    e.script = s.to_string();
+                    self.voicevox.say(e);
+                }
+                thread::sleep(Duration::from_secs(60));
+                let _ = self.spoon.close();
+                thread::sleep(Duration::from_secs(60 * 60 * 24 * 31));
+            } else {
+                self.queue_comment(&s, Priority::Normal);
+                if (self.config.voicevox.enabled) {
+                    e.script = s;
+                    self.voicevox.say(e);
+                }
+            }
+        }
+    }
+
     pub fn process_comments(&mut self) -> Result<(), Box<dyn Error>> {
+        self.flush_comments();
+
         self.process_guide().unwrap_or_else(|e| error!("{}", e));
 
-        if (self.config.chatgpt.enabled) {
-            for e in self.chatgpt.fetch() {
-                let s = e.script.trim();
-                if (s == "QUOTA_ERROR") {
-                    let _ = Command::new("curl")
-                        .args([
-                            &self.config.chatgpt.discord_url,
-                            "-d",
-                            r#"{"wait": true, "content": "OpenAI API quota exceeded"}"#,
-                            "-H",
-                            "Content-Type: application/json",
-                        ])
-                        .status();
-                    let s = "AI部分にエラーが発生しました。管理人に通知を送信しました。一分後、枠を終了します。申し訳ございません。";
-                    let _ = self.spoon.post_comment(s);
-                    if (self.config.voicevox.enabled) {
-                        self.voicevox.say(Script::new(s, e.effect, e.speaker));
-                    }
-                    thread::sleep(Duration::from_secs(60));
-                    let _ = self.z.close();
-                    thread::sleep(Duration::from_secs(60 * 60 * 24 * 31));
-                } else {
-                    self.spoon
-                        .post_comment(s)
-                        .unwrap_or_else(|e| error!("{}", e));
-                    if (self.config.voicevox.enabled) {
-                        self.voicevox.say(Script::new(s, e.effect, e.speaker));
-                    }
-                }
-            }
+        self.process_chatgpt_replies();
+
+        for e in self.websocket.fetch_connection_events() {
+            self.process_connection_event(e)
+                .unwrap_or_else(|e| error!("{}", e));
         }
 
         let comments = self.websocket.fetch();
@@ -642,127 +775,218 @@ impl SpoonClient {
         }
 
         for s in comments {
-            //for performance
-            if (s.starts_with(r#"{"event":"live_update","#)
-                || s.starts_with(r#"{"event":"live_rank","#))
-            {
+            //for performance (`live_update` is not skipped as it carries `msg_interval`)
+            if (s.starts_with(r#"{"event":"live_rank","#)) {
                 continue;
             }
 
-            let m: Map<String, Value> = match serde_json::from_str::<Value>(&s) {
-                Ok(v) => match v.as_object() {
-                    Some(m) => m.clone(),
-                    None => {
-                        error!("WebSocket message is not a JSON object: {}", v);
-                        continue;
-                    }
-                },
+            let event = match s.parse::<SpoonEvent>() {
+                Ok(e) => e,
                 Err(e) => {
-                    error!("WebSocket message is not JSON: {} in {}", e, s);
+                    error!("deserialization error: {} in {}", e, s);
                     continue;
                 }
             };
+            self.process_event(event)
+                .unwrap_or_else(|e| error!("{}", e));
+        }
 
-            let event_type = match m.get("event") {
-                None => {
-                    error!("no event field found in object: {}", s);
-                    continue;
-                }
-                Some(Value::String(s)) => s,
-                _ => {
-                    error!("event field is not a string: {}", s);
-                    continue;
-                }
-            };
+        Ok(())
+    }
 
-            match event_type.as_str() {
-                "live_join" => {
-                    let o = match serde_json::from_str::<LiveJoin>(&s) {
-                        Ok(o) => o,
-                        Err(e) => {
-                            error!("deserialization error: {} in {}", e, s);
-                            continue;
-                        }
-                    };
-                    if (o.result.detail != "success") {
-                        panic!("WebSocket connection failed. Note fan live is not supported as we connect WebSocket anonymously: {:?}", o);
-                    }
-                    info!("WebSocket connection succeeded.");
-                }
-                "live_rank" => (),
-                "live_update" => (),
-                //comment
-                "live_message" => {
-                    let o = match serde_json::from_str::<LiveMessage>(&s) {
-                        Ok(o) => o,
-                        Err(e) => {
-                            error!("deserialization error: {} in {}", e, s);
-                            continue;
-                        }
-                    };
-                    self.process_message_comment(o)
-                        .unwrap_or_else(|e| error!("{}", e));
-                }
-                //heart
-                "live_like" => {
-                    let o = match serde_json::from_str::<LiveLike>(&s) {
-                        Ok(o) => o,
-                        Err(e) => {
-                            error!("deserialization error: {} in {}", e, s);
-                            continue;
-                        }
-                    };
-                    self.process_like_comment(o)
-                        .unwrap_or_else(|e| error!("{}", e));
-                }
-                //粗品
-                "use_item" => {
-                    let o = match serde_json::from_str::<UseItem>(&s) {
-                        Ok(o) => o,
-                        Err(e) => {
-                            error!("deserialization error: {} in {}", e, s);
-                            continue;
-                        }
-                    };
-                    self.process_use_item_comment(o)
-                        .unwrap_or_else(|e| error!("{}", e));
-                }
-                //buster
-                "live_present_like" => {
-                    let o = match serde_json::from_str::<LivePresentLike>(&s) {
-                        Ok(o) => o,
-                        Err(e) => {
-                            error!("deserialization error: {} in {}", e, s);
-                            continue;
-                        }
-                    };
-                    self.process_present_like_comment(o)
-                        .unwrap_or_else(|e| error!("{}", e));
-                }
-                //spoon
-                "live_present" => {
-                    let o = match serde_json::from_str::<LivePresent>(&s) {
-                        Ok(o) => o,
-                        Err(e) => {
-                            error!("deserialization error: {} in {}", e, s);
-                            continue;
-                        }
-                    };
-                    self.process_present_comment(o)
-                        .unwrap_or_else(|e| error!("{}", e));
+    fn process_connection_event(&mut self, e: ConnectionEvent) -> Result<(), Box<dyn Error>> {
+        match e {
+            ConnectionEvent::Reconnected {
+                downtime,
+                num_retry,
+            } => {
+                let c = self.messages.render(
+                    "reconnected",
+                    &[(
+                        "downtime",
+                        &self.messages.locale().pretty_print_duration(downtime),
+                    )],
+                );
+                self.logger.log(
+                    Some(constant::COLOR_RED),
+                    &format!("{} (retry: {})", c, num_retry),
+                )?;
+                if (self.config.spoon.websocket.should_comment_reconnection) {
+                    self.queue_comment(&c, Priority::High);
                 }
-                t => {
-                    error!("unknown event type: {} in {}", t, s);
-                    continue;
+            }
+            ConnectionEvent::Latency(latency) => {
+                info!("WebSocket latency: {}ms", latency.as_millis());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn process_event(&mut self, event: SpoonEvent) -> Result<(), Box<dyn Error>> {
+        match event {
+            SpoonEvent::LiveJoin(o) => {
+                //This also arrives after each reconnection, so it must not bring the bot down.
+                if (o.result.detail != "success") {
+                    return Err(format!("WebSocket connection failed. Note fan live is not supported as we connect WebSocket anonymously: {:?}", o).into());
                 }
+                info!("WebSocket connection succeeded.");
+                Ok(())
             }
+            SpoonEvent::LiveRank(_) => Ok(()),
+            SpoonEvent::LiveUpdate(o) => {
+                self.update_comment_interval(o.data.live.msg_interval);
+                Ok(())
+            }
+            SpoonEvent::LiveMessage(o) => self.process_message_comment(o),
+            SpoonEvent::LiveLike(o) => {
+                self.update_comment_interval(o.data.live.msg_interval);
+                self.process_like_comment(o)
+            }
+            SpoonEvent::UseItem(o) => self.process_use_item_comment(o),
+            SpoonEvent::LivePresentLike(o) => self.process_present_like_comment(o),
+            SpoonEvent::LivePresent(o) => {
+                self.update_comment_interval(o.data.live.msg_interval);
+                self.process_present_comment(o)
+            }
+            SpoonEvent::Unknown(v) => Err(format!("unknown or malformed event: {}", v).into()),
+        }
+    }
+
+    //applies `write` after the ones buffered so far
+    fn write_database(&mut self, write: PendingWrite) {
+        self.pending_writes.push(write);
+        if let Err(e) = self.pending_writes.flush_if_due(&self.database) {
+            error!(
+                "{} writes to the database are buffered: {}",
+                self.pending_writes.len(),
+                e
+            );
         }
+    }
 
-        Ok(())
+    //celebrates the milestones the listener has achieved for the first time
+    fn celebrate_milestones(&mut self, locale: Locale, nickname: &str, entity: &ListenerEntity) {
+        let now = Local::now();
+        let mut new_milestones = vec![];
+        for milestone in loyalty::reached_milestones(&self.config.spoon.loyalty, entity) {
+            match self.database.insert_milestone(entity.id, milestone, now) {
+                Ok(true) => new_milestones.push(milestone),
+                Ok(false) => (),
+                Err(e) => error!("{}", e),
+            }
+        }
+        for milestone in loyalty::milestones_to_celebrate(&new_milestones) {
+            self.celebrate(locale, nickname, milestone);
+        }
+    }
+
+    //The spoons sent before the gifts were recorded are unknown, so we celebrate only the listeners who have been recorded to send no spoon but `amount`.
+    fn celebrate_first_spoon(&mut self, locale: Locale, nickname: &str, id: usize, amount: i64) {
+        let is_first = match self.database.total_spoons(id) {
+            Ok(n) => n == amount,
+            Err(e) => {
+                error!("{}", e);
+                return;
+            }
+        };
+        match self
+            .database
+            .insert_milestone(id, Milestone::FirstSpoon, Local::now())
+        {
+            Ok(true) if (is_first) => self.celebrate(locale, nickname, Milestone::FirstSpoon),
+            Ok(_) => (),
+            Err(e) => error!("{}", e),
+        }
+    }
+
+    fn celebrate(&mut self, locale: Locale, nickname: &str, milestone: Milestone) {
+        let c = match milestone {
+            Milestone::VisitCount(n) => self.messages.render_in(
+                locale,
+                "milestone_visit_count",
+                &[("nickname", nickname), ("visit_count", &n.to_string())],
+            ),
+            Milestone::StayHours(n) => self.messages.render_in(
+                locale,
+                "milestone_stay_hours",
+                &[("nickname", nickname), ("hours", &n.to_string())],
+            ),
+            Milestone::FirstSpoon => {
+                self.messages
+                    .render_in(locale, "milestone_first_spoon", &[("nickname", nickname)])
+            }
+        };
+        self.logger
+            .log(Some(constant::COLOR_YELLOW), &c)
+            .unwrap_or_else(|e| error!("{}", e));
+        self.queue_comment(&c, Priority::Normal);
+        if (self.config.voicevox.enabled) {
+            self.voicevox.say(Script::new(
+                &c,
+                AudioEffect::default(),
+                self.config.voicevox.speaker,
+            ));
+        }
+        let loyalty = &self.config.spoon.loyalty;
+        if (!loyalty.stinger_file.is_empty()) {
+            self.bgm.push(&Audio::new(
+                &loyalty.stinger_file,
+                loyalty.stinger_volume,
+                AudioEffect::default(),
+            ));
+        }
+    }
+
+    //`listener_stats` (or `first_visit_stats`) shown beside the greeting, which is `None` while the database is unavailable
+    fn listener_stats(&self, locale: Locale, id: usize, is_first_visit: bool) -> Option<String> {
+        let f = || -> Result<Option<String>, DatabaseError> {
+            let (entity, rank) = match (
+                self.database.select_by_id(id)?,
+                self.database.select_rank(id)?,
+            ) {
+                (Some(entity), Some(rank)) => (entity, rank),
+                _ => return Ok(None), //still buffered
+            };
+            let num_listeners = self.database.count()?;
+            let ret = if (is_first_visit) {
+                //A newcomer is ranked last, below the listeners who came earlier with the same values.
+                //(`select_rank()` breaks ties by the user id, which doesn't tell who came first.)
+                self.messages.render_in(
+                    locale,
+                    "first_visit_stats",
+                    &[
+                        ("rank", &num_listeners.to_string()),
+                        ("num_listeners", &num_listeners.to_string()),
+                    ],
+                )
+            } else {
+                self.messages.render_in(
+                    locale,
+                    "listener_stats",
+                    &[
+                        ("visit_count", &entity.visit_count.to_string()),
+                        ("stay", &locale.pretty_print_duration(entity.stay_duration)),
+                        ("rank", &rank.to_string()),
+                        ("num_listeners", &num_listeners.to_string()),
+                    ],
+                )
+            };
+            Ok(Some(ret))
+        };
+        f().unwrap_or_else(|e| {
+            error!("{}", e);
+            None
+        })
     }
 
     pub fn process_listeners(&mut self, config: &Config) -> Result<(), Box<dyn Error>> {
-        let listeners_set = self
+        if (!self.pending_writes.is_empty()) {
+            if let Err(e) = self.pending_writes.flush_if_due(&self.database) {
+                error!("{}", e);
+            }
+        }
+
+        let mut listeners_set = self
             .spoon
             .retrieve_listeners()?
             .into_iter()
@@ -772,21 +996,28 @@ impl SpoonClient {
         let new_listeners = &listeners_set - &self.previous_listeners_set;
 
         for e in exited_listeners {
-            let c = format!("{}さん、また来てね。", e.nickname);
+            let locale = self.listener_locale(e.id);
+            let c = self
+                .messages
+                .render_in(locale, "goodbye", &[("nickname", &e.nickname)]);
             let stay_duration = self.previous_listeners_map.get(&e).unwrap().elapsed();
             let c_with_time = format!(
-                "{}(滞在時間: {})",
+                "{}({})",
                 c,
-                util::pretty_print_duration(stay_duration),
+                self.messages.render_in(
+                    locale,
+                    "stay",
+                    &[("stay", &locale.pretty_print_duration(stay_duration))]
+                ),
             );
-            {
-                let mut entity = self.database.select_by_id(e.id).unwrap();
-                entity.stay_duration += stay_duration;
-                self.database.update(entity);
-            }
+            self.write_database(PendingWrite::EndVisit {
+                listener_id: e.id,
+                live_id: self.spoon.live_id(),
+                left_at: Local::now(),
+            });
             self.logger.log(Some(constant::COLOR_GREEN), &c_with_time)?;
             if (config.spoon.should_comment_listener) {
-                self.spoon.post_comment(&c_with_time)?;
+                self.queue_comment(&c_with_time, Priority::Low);
                 if (config.voicevox.enabled) {
                     self.voicevox.say(Script::new(
                         &c,
@@ -799,113 +1030,131 @@ impl SpoonClient {
         }
 
         for e in new_listeners {
+            //If the database is unavailable, the listener is treated as not joined yet and retried next time,
+            // so that e.g. a listener tagged with `#block` is not let in just because the tags couldn't be read.
+            let read = || -> Result<_, DatabaseError> {
+                Ok((
+                    self.database.select_tags(e.id)?,
+                    self.database.select_by_id(e.id)?,
+                    self.database.select_notes(e.id)?,
+                ))
+            };
+            let (tags, known_entity, notes) = match read() {
+                Ok(r) => r,
+                Err(err) => {
+                    error!("Deferred greeting {:?}: {}", e, err);
+                    listeners_set.remove(&e);
+                    continue;
+                }
+            };
+            if (notes::should_block(&tags)) {
+                match self.spoon.block_listener(e.id) {
+                    Ok(()) => info!("Blocked {:?} tagged with #{}.", e, notes::TAG_BLOCK),
+                    Err(err) => error!("{}", err),
+                }
+                //treated as if not joined so that neither a goodbye is said nor the visit is recorded
+                //(If the block failed, it is retried next time.)
+                listeners_set.remove(&e);
+                continue;
+            }
+
+            let locale = self.listener_locale(e.id);
             self.previous_listeners_map
                 .insert(e.clone(), Instant::now());
+            let live_id = self.spoon.live_id();
+            let now = Local::now();
 
-            let get_ranking = || -> (usize, usize) {
-                let all_entities = self
-                    .database
-                    .select_all()
-                    .into_iter()
-                    .sorted_by_key(|e| (e.stay_duration, e.visit_count))
-                    .rev()
-                    .collect_vec();
-
-                let ranking = all_entities
-                    .iter()
-                    .position(|entity| entity.id == e.id)
-                    .unwrap()
-                    + 1;
-
-                (ranking, all_entities.len())
-            };
-
-            //おかえりなさい
-            if (self
+            let is_rejoin = self
                 .cumulative_listeners
                 .iter()
-                .any(|listener| listener.id == e.id))
-            {
-                let entity = self.database.select_by_id(e.id).unwrap();
-                if (entity.name != e.nickname) {
-                    let mut entity = entity.clone();
-                    entity.name = e.nickname.clone();
-                    self.database.update(entity);
-                }
-                let ranking = get_ranking();
-                #[allow(clippy::format_in_format_args)]
-                let c = format!(
-                    "{}さん、おかえりなさい。\n({})",
-                    e.nickname,
-                    format!(
-                        "訪問回数: {}回 / 滞在時間: {} / ランキング: {}位/{}人中",
-                        entity.visit_count,
-                        util::pretty_print_duration(entity.stay_duration),
-                        ranking.0,
-                        ranking.1,
-                    )
-                );
-                self.logger.log(Some(constant::COLOR_GREEN), &c)?;
-                if (config.spoon.should_comment_listener) {
-                    self.spoon.post_comment(&c)?;
-                    if (config.voicevox.enabled) {
-                        self.voicevox.say(Script::new(
-                            c.split('\n').next().unwrap(),
-                            AudioEffect::default(),
-                            config.voicevox.speaker,
-                        ));
-                    }
-                }
+                .any(|listener| listener.id == e.id);
+            let is_first_visit = !is_rejoin && known_entity.is_none();
 
-            //いらっしゃい
-            } else {
+            if (!is_rejoin) {
                 self.cumulative_listeners.insert(e.clone());
-                let c = format!(
-                    "{}さん、いらっしゃい。\n({})",
-                    e.nickname,
-                    if let Some(mut entity) = self.database.select_by_id(e.id) {
-                        entity.name = e.nickname.clone();
-                        entity.visit_count += 1;
-                        self.database.update(entity.clone());
-
-                        let ranking = get_ranking();
-
-                        format!(
-                            "訪問回数: {}回 / 滞在時間: {} / ランキング: {}位/{}人中",
-                            entity.visit_count,
-                            util::pretty_print_duration(entity.stay_duration),
-                            ranking.0,
-                            ranking.1,
-                        )
+                self.write_database(PendingWrite::InsertListener(ListenerEntity::new(
+                    e.id,
+                    e.nickname.clone(),
+                    e.tag.clone(),
+                    0,
+                    Duration::default(),
+                )));
+            }
+            self.write_database(PendingWrite::UpdateName {
+                id: e.id,
+                name: e.nickname.clone(),
+                tag: e.tag.clone(),
+                seen_at: now,
+            });
+            self.write_database(PendingWrite::StartVisit {
+                listener_id: e.id,
+                live_id,
+                joined_at: now,
+            });
+
+            let entity = self.database.select_by_id(e.id).unwrap_or_else(|err| {
+                error!("{}", err);
+                None
+            });
+            let loyalty = &config.spoon.loyalty;
+            let tier = entity
+                .as_ref()
+                .filter(|_| loyalty.enabled)
+                .and_then(|entity| loyalty::tier(loyalty, entity));
+
+            //おかえりなさい or いらっしゃい
+            let custom_greeting = notes::greeting(&tags, &e.nickname);
+            let greeting = match (&custom_greeting, tier) {
+                (Greeting::Custom(s), _) => s.clone(),
+                (_, None) => self.messages.render_in(
+                    locale,
+                    if (is_rejoin) {
+                        "welcome_back"
                     } else {
-                        let entity =
-                            ListenerEntity::new(e.id, e.nickname.clone(), 1, Duration::default());
-                        self.database.insert(entity);
-
-                        let entities = self.database.select_all();
-
-                        format!(
-                            "初見さん / ランキング: {}位/{}人中",
-                            entities.len(),
-                            entities.len()
-                        )
-                    }
-                );
-                self.logger.log(
-                    Some(constant::COLOR_GREEN),
-                    &format!("{} ({:?})", c, e), //We print `e` itself to trace the unique user id of a troll.
-                )?;
-                if (config.spoon.should_comment_listener) {
-                    self.spoon.post_comment(&c)?;
-                    if (config.voicevox.enabled) {
-                        self.voicevox.say(Script::new(
-                            c.split('\n').next().unwrap(),
-                            AudioEffect::default(),
-                            config.voicevox.speaker,
-                        ));
-                    }
+                        "welcome"
+                    },
+                    &[("nickname", &e.nickname)],
+                ),
+                (_, Some(tier)) => self.messages.render_in(
+                    locale,
+                    if (is_rejoin) {
+                        "welcome_back_tier"
+                    } else {
+                        "welcome_tier"
+                    },
+                    &[("nickname", &e.nickname), ("tier", &tier.name)],
+                ),
+            };
+            let c = match self.listener_stats(locale, e.id, is_first_visit) {
+                None => greeting,
+                Some(stats) => format!("{}\n({})", greeting, stats),
+            };
+            let mut line = if (is_rejoin) {
+                c.clone()
+            } else {
+                format!("{} ({:?})", c, e) //We print `e` itself to trace the unique user id of a troll.
+            };
+            if let Some(summary) = notes::summary(&notes, &tags) {
+                line = format!("{} [{}]", line, summary);
+            }
+            self.logger.log(Some(constant::COLOR_GREEN), &line)?;
+            if (custom_greeting == Greeting::Suppressed) {
+                continue;
+            }
+            if (config.spoon.should_comment_listener) {
+                self.queue_comment(&c, Priority::Low);
+                if (config.voicevox.enabled) {
+                    self.voicevox.say(Script::new(
+                        c.split('\n').next().unwrap(),
+                        AudioEffect::default(),
+                        config.voicevox.speaker,
+                    ));
                 }
             }
+
+            if let Some(entity) = entity.filter(|_| loyalty.enabled) {
+                self.celebrate_milestones(locale, &e.nickname, &entity);
+            }
         }
 
         self.previous_listeners_set = listeners_set;
@@ -923,8 +1172,35 @@ impl SpoonClient {
         }
         let s = fs::read_to_string(p)?.trim().to_string();
         fs::remove_file(p)?;
+        //e.g. `!note 123 likes cats`, which is executed instead of being posted
+        if let Some(command) = s.strip_prefix('!') {
+            let args = command
+                .split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>();
+            let mode = args.first().map_or("", |mode| mode.as_str());
+            let result = if (notes::COMMANDS.contains(&mode)) {
+                Some(notes::execute(&self.database, &args, Local::now()))
+            } else if (pronunciation::COMMANDS.contains(&mode)) {
+                let ret = pronunciation::execute(&self.database, &args, Local::now());
+                //applied right away
+                self.voicevox
+                    .set_dictionary(Self::load_dictionary(&self.database));
+                Some(ret)
+            } else {
+                None
+            };
+            if let Some(result) = result {
+                match result {
+                    Ok(s) => info!("{}", s),
+                    Err(e) => error!("{}", e),
+                }
+                return Ok(());
+            }
+        }
         if (!s.is_empty()) {
-            self.spoon.post_comment(&format!("(運営より) {}", s))?;
+            let c = self.messages.render("tunnel", &[("message", &s)]);
+            self.queue_comment(&c, Priority::High);
             if (self.config.voicevox.enabled) {
                 self.voicevox.say(Script::new(
                     &s,
@@ -939,10 +1215,560 @@ impl SpoonClient {
 
 impl Drop for SpoonClient {
     fn drop(&mut self) {
-        for (listener, instant) in &self.previous_listeners_map {
-            let mut entity = self.database.select_by_id(listener.id).unwrap();
-            entity.stay_duration += instant.elapsed();
-            self.database.update(entity);
+        let now = Local::now();
+        //Waiting for the lock no longer stalls anything.
+        if let Err(e) = self.database.set_busy_timeout(database::BUSY_TIMEOUT) {
+            error!("{}", e);
+        }
+        self.pending_writes.push(PendingWrite::EndAllVisits {
+            live_id: self.spoon.live_id(),
+            left_at: now,
+        });
+        if let Err(e) = self.pending_writes.flush(&self.database) {
+            error!("{}", e);
+        }
+        if (!self.pending_writes.is_empty()) {
+            error!(
+                "{} writes to the database are lost.",
+                self.pending_writes.len()
+            );
+        }
+        if let Err(e) = self.database.end_live(self.spoon.live_id(), now) {
+            error!("{}", e);
+        }
+
+        if (!self.config.backup_dir.is_empty()) {
+            let path = archive::backup_path(&self.config.backup_dir, now);
+            match self.database.backup(&path) {
+                Ok(()) => info!("Backed up the database to [ {} ].", path),
+                Err(e) => error!("Failed to back up the database to [ {} ]: {}", path, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::backend::FakeBackend;
+    use std::fs;
+    use std::net::TcpListener;
+
+    use super::super::config::{self, Loyalty, Tier, TtsEngine, VoicePreset, VoiceVoxBackend};
+    use super::super::locale::Locale;
+    use super::super::test_server::serve;
+    use super::*;
+
+    fn new_client(config: Config) -> (SpoonClient, Rc<FakeBackend>) {
+        let config = Config {
+            database_file: ":memory:".to_string(),
+            ..config
+        };
+        let spoon = Rc::new(FakeBackend::new(1));
+        let client = SpoonClient::with_backend(Rc::new(config), spoon.clone()).unwrap();
+        (client, spoon)
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let mut config = Config::default();
+        config.spoon.should_comment_heart = true;
+        let (mut client, spoon) = new_client(config);
+
+        let mut o = LiveLike::default();
+        o.data.author.nickname = "Mike".to_string();
+        client.process_event(SpoonEvent::LiveLike(o)).unwrap();
+        client.flush_comments();
+        assert_eq!(vec!["Mikeさん、ハートありがとう。"], spoon.comments());
+        assert_eq!(1, client.database.select_gifts_by_live(1).unwrap().len());
+
+        //a burst of hearts is thanked for at once
+        spoon.clear_comments();
+        for nickname in ["Lisa", "Mike", "Lisa"] {
+            let mut o = LiveLike::default();
+            o.data.author.nickname = nickname.to_string();
+            client.process_event(SpoonEvent::LiveLike(o)).unwrap();
         }
+        client.flush_comments();
+        assert_eq!(
+            vec!["Lisaさん、Mikeさん、ハートありがとう。"],
+            spoon.comments()
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let mut config = Config::default();
+        config.spoon.should_comment_listener = true;
+        let (mut client, spoon) = new_client(config.clone());
+
+        let listener = Listener {
+            id: 10,
+            nickname: "Lisa".to_string(),
+            tag: "lisa".to_string(),
+        };
+        spoon.set_listeners(vec![listener.clone()]);
+        client.process_listeners(&config).unwrap();
+        client.flush_comments();
+        assert_eq!(
+            vec!["Lisaさん、いらっしゃい。\n(初見さん / ランキング: 1位/1人中)"],
+            spoon.comments()
+        );
+
+        spoon.clear_comments();
+        spoon.set_listeners(vec![]);
+        client.process_listeners(&config).unwrap();
+        client.flush_comments();
+        assert_eq!(1, spoon.comments().len());
+        assert!(spoon.comments()[0].starts_with("Lisaさん、また来てね。"));
+
+        spoon.clear_comments();
+        spoon.set_listeners(vec![listener]);
+        client.process_listeners(&config).unwrap();
+        client.flush_comments();
+        assert_eq!(1, spoon.comments().len());
+        assert!(spoon.comments()[0].starts_with("Lisaさん、おかえりなさい。"));
+
+        //A newcomer is ranked last even if the user id is smaller.
+        spoon.clear_comments();
+        spoon.set_listeners(vec![Listener {
+            id: 5,
+            nickname: "Mike".to_string(),
+            tag: "mike".to_string(),
+        }]);
+        client.process_listeners(&config).unwrap();
+        client.flush_comments();
+        assert!(spoon
+            .comments()
+            .contains(&"Mikeさん、いらっしゃい。\n(初見さん / ランキング: 2位/2人中)".to_string()));
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        let mut config = Config::default();
+        config.spoon.should_comment_heart = true;
+        config.spoon.locale = Locale::En;
+        config.spoon.should_use_listener_locale = true;
+        let (mut client, spoon) = new_client(config);
+
+        let mut o = LiveLike::default();
+        o.data.author.id = 20;
+        o.data.author.nickname = "Mike".to_string();
+        client
+            .process_event(SpoonEvent::LiveLike(o.clone()))
+            .unwrap();
+        o.data.author.country = "kr".to_string();
+        client
+            .process_event(SpoonEvent::LiveLike(o.clone()))
+            .unwrap();
+        //remembered even if the country is missing
+        o.data.author.country = "".to_string();
+        client.process_event(SpoonEvent::LiveLike(o)).unwrap();
+        client.flush_comments();
+        assert_eq!(
+            vec!["Thank you for the heart, Mike!", "Mike님, 하트 감사합니다."],
+            spoon.comments()
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test04() {
+        //commands are available even if ChatGPT is disabled
+        let (mut client, spoon) = new_client(Config::default());
+
+        let mut o = LiveMessage::default();
+        o.data.user.id = 30;
+        o.data.user.nickname = "Mike".to_string();
+        o.update_component.message.value = "/help".to_string();
+        client
+            .process_event(SpoonEvent::LiveMessage(o.clone()))
+            .unwrap();
+        o.update_component.message.value = "/omikuji".to_string();
+        client
+            .process_event(SpoonEvent::LiveMessage(o.clone()))
+            .unwrap();
+        //not a command
+        o.update_component.message.value = "/echo hello".to_string();
+        client.process_event(SpoonEvent::LiveMessage(o)).unwrap();
+        client.flush_comments();
+
+        let comments = spoon.comments();
+        assert_eq!(2, comments.len());
+        assert!(comments[0].starts_with("[💡ヘルプ]\n/help: このヘルプを表示\n/bgm: BGMを変更\n"));
+        assert!(comments[0].contains("\n/fortune /omikuji: "));
+        assert!(comments[1].starts_with("🔮 Mikeさん\n"));
+    }
+
+    #[test]
+    // #[ignore]
+    fn test05() {
+        let config = Config::default();
+        let (mut client, spoon) = new_client(config.clone());
+
+        let mut listener = Listener {
+            id: 10,
+            nickname: "Lisa".to_string(),
+            tag: "lisa".to_string(),
+        };
+        spoon.set_listeners(vec![listener.clone()]);
+        client.process_listeners(&config).unwrap();
+        spoon.set_listeners(vec![]);
+        client.process_listeners(&config).unwrap();
+        listener.nickname = "Troll".to_string();
+        spoon.set_listeners(vec![listener]);
+        client.process_listeners(&config).unwrap();
+
+        let mut o = LiveMessage::default();
+        o.data.user.id = 30;
+        o.update_component.message.value = "/whois lisa".to_string();
+        client
+            .process_event(SpoonEvent::LiveMessage(o.clone()))
+            .unwrap();
+        o.data.user.is_fixedmng = true;
+        client.process_event(SpoonEvent::LiveMessage(o)).unwrap();
+        client.flush_comments();
+
+        let comments = spoon.comments();
+        assert_eq!(2, comments.len());
+        assert_eq!("`/whois`は管理者専用のコマンドだよ。", comments[0]);
+        assert!(comments[1].starts_with("🔍 Troll (@lisa)\nID: 10 / "));
+        assert!(comments[1].ends_with("Lisa(@lisa) → Troll(@lisa)"));
+    }
+
+    #[test]
+    // #[ignore]
+    fn test06() {
+        let mut config = Config::default();
+        config.spoon.should_comment_listener = true;
+        config.spoon.loyalty = Loyalty {
+            enabled: true,
+            tiers: vec![Tier {
+                name: "ブロンズ".to_string(),
+                min_stay_hours: 10,
+            }],
+            visit_count_milestones: vec![5, 10],
+            should_celebrate_first_spoon: true,
+            ..Default::default()
+        };
+        let (mut client, spoon) = new_client(config.clone());
+
+        //visited 9 times before
+        client
+            .database
+            .insert(ListenerEntity::new(
+                10,
+                "Lisa".to_string(),
+                "lisa".to_string(),
+                9,
+                Duration::from_secs(3600 * 10),
+            ))
+            .unwrap();
+        spoon.set_listeners(vec![Listener {
+            id: 10,
+            nickname: "Lisa".to_string(),
+            tag: "lisa".to_string(),
+        }]);
+        client.process_listeners(&config).unwrap();
+        client.flush_comments();
+        let comments = spoon.comments();
+        assert_eq!(2, comments.len());
+        assert_eq!("Lisaさん、10回目の訪問ありがとう！", comments[0]);
+        assert!(comments[1].starts_with("ブロンズのLisaさん、いらっしゃい。\n(訪問回数: 10回"));
+
+        spoon.clear_comments();
+        let mut o = LivePresent::default();
+        o.data.author.id = 10;
+        o.data.author.nickname = "Lisa".to_string();
+        o.data.amount = 10;
+        o.data.combo = 1;
+        client
+            .process_event(SpoonEvent::LivePresent(o.clone()))
+            .unwrap();
+        client.process_event(SpoonEvent::LivePresent(o)).unwrap();
+        client.flush_comments();
+        assert_eq!(
+            vec!["Lisaさん、初めてのスプーンありがとう！"],
+            spoon.comments()
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test07() {
+        let tunnel = std::env::temp_dir().join("spoon_comment_viewer_client_test07.txt");
+        let mut config = Config::default();
+        config.spoon.should_comment_listener = true;
+        config.spoon.message_tunnel_file = tunnel.to_str().unwrap().to_string();
+        let (mut client, spoon) = new_client(config.clone());
+
+        for command in [
+            "!note 10 likes cats",
+            "!tag 10 greeting {nickname}さん、お誕生日おめでとう！",
+            "!tag 20 no_greeting",
+            "!tag 30 block",
+        ] {
+            fs::write(&tunnel, command).unwrap();
+            client.process_message_tunnel().unwrap();
+        }
+        client.flush_comments();
+        assert!(spoon.comments().is_empty());
+        assert_eq!(1, client.database.select_notes(10).unwrap().len());
+
+        let listener = |id: usize, nickname: &str| Listener {
+            id,
+            nickname: nickname.to_string(),
+            tag: nickname.to_lowercase(),
+        };
+        spoon.set_listeners(vec![
+            listener(10, "Lisa"),
+            listener(20, "Troll"),
+            listener(30, "Spammer"),
+        ]);
+        client.process_listeners(&config).unwrap();
+        client.flush_comments();
+        let comments = spoon.comments();
+        assert_eq!(1, comments.len());
+        assert!(comments[0].starts_with("Lisaさん、お誕生日おめでとう！\n"));
+        assert_eq!(vec![30], spoon.blocked_listener_ids());
+        assert!(client.database.select_by_id(30).unwrap().is_none());
+
+        //No goodbye is said to the blocked listener.
+        spoon.clear_comments();
+        spoon.set_listeners(vec![listener(10, "Lisa"), listener(20, "Troll")]);
+        client.process_listeners(&config).unwrap();
+        client.flush_comments();
+        assert!(spoon.comments().is_empty());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test08() {
+        let mut config = Config::default();
+        config.voicevox.voices = ["zundamon", "asmr", "sayo"]
+            .into_iter()
+            .map(|name| VoicePreset {
+                name: name.to_string(),
+                speaker: if (name == "sayo") { 46 } else { 3 },
+                ..Default::default()
+            })
+            .collect();
+        let (mut client, spoon) = new_client(config);
+
+        let mut o = LiveMessage::default();
+        o.data.user.id = 30;
+        o.data.user.nickname = "Mike".to_string();
+        for s in ["/myvoice sayo", "/myvoice unknown", "/myvoice"] {
+            o.update_component.message.value = s.to_string();
+            client
+                .process_event(SpoonEvent::LiveMessage(o.clone()))
+                .unwrap();
+        }
+        assert_eq!(
+            Some(46),
+            command::preset_of_listener(&client.config.voicevox, &client.database, 30)
+                .map(|preset| preset.speaker)
+        );
+        o.update_component.message.value = "/myvoice reset".to_string();
+        client
+            .process_event(SpoonEvent::LiveMessage(o.clone()))
+            .unwrap();
+        assert_eq!(None, client.database.select_voice(30).unwrap());
+        client.flush_comments();
+
+        let comments = spoon.comments();
+        assert_eq!(4, comments.len());
+        assert_eq!("Mikeさんの声をsayoにしたよ。", comments[0]);
+        assert_eq!(
+            "`unknown`という声はないよ。\n声: zundamon, asmr, sayo",
+            comments[1]
+        );
+        assert!(comments[2].starts_with("「/myvoice　zundamon」のように使ってね。"));
+        assert_eq!("Mikeさんの声を元に戻したよ。", comments[3]);
+
+        //The voices in `/help` are from the config.
+        spoon.clear_comments();
+        o.update_component.message.value = "/help".to_string();
+        client.process_event(SpoonEvent::LiveMessage(o)).unwrap();
+        client.flush_comments();
+        assert!(spoon.comments()[0].contains("\necho, zundamon, asmr, sayo のどれかを\n"));
+    }
+
+    #[test]
+    // #[ignore]
+    fn test09() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://127.0.0.1:{}/",
+            listener.local_addr().unwrap().port()
+        );
+        let handle = serve(
+            listener,
+            vec![
+                (200, r#"[{"styles":[{"id":3},{"id":46}]}]"#.to_string()),
+                (200, r#"{"choices":[{"text":"こんにちは。"}]}"#.to_string()),
+                (200, "{}".to_string()),
+                (200, "RIFF".to_string()),
+            ],
+        );
+        let output_dir = std::env::temp_dir().join("spoon_comment_viewer_client_test09");
+        let _ = fs::remove_dir_all(&output_dir);
+        fs::create_dir(&output_dir).unwrap();
+
+        let mut config = Config::default();
+        config.chatgpt.enabled = true;
+        config.chatgpt.http = config::HTTP {
+            url: format!("{}completions", url),
+            timeout_ms: 3000,
+        };
+        config.chatgpt.model.max_tokens_ja = 100;
+        config.voicevox = config::VoiceVox {
+            enabled: true,
+            backend: VoiceVoxBackend::Engine,
+            engine: config::Engine {
+                url,
+                ..Default::default()
+            },
+            routes: HashMap::from([(Locale::Ja, TtsEngine::VoiceVox)]),
+            speaker: 3,
+            speed: 1.0,
+            voices: vec![VoicePreset {
+                name: "sayo".to_string(),
+                speaker: 46,
+                speed: Some(1.3),
+                pitch: Some(0.05),
+                ..Default::default()
+            }],
+            output_dir: output_dir.to_str().unwrap().to_string(),
+            timeout_sec: 10,
+            ..Default::default()
+        };
+        let (mut client, spoon) = new_client(config);
+
+        let mut o = LiveMessage::default();
+        o.data.user.id = 30;
+        o.data.user.nickname = "Mike".to_string();
+        o.update_component.message.value = "/sayo こんにちは".to_string();
+        client.process_event(SpoonEvent::LiveMessage(o)).unwrap();
+        let start = Instant::now();
+        while (spoon.comments().is_empty()) {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+            client.process_chatgpt_replies();
+            client.flush_comments();
+        }
+        assert_eq!(vec!["こんにちは。"], spoon.comments());
+
+        //The reply is read aloud in the voice of the comment.
+        let requests = handle.join().unwrap();
+        assert!(requests[3].starts_with("POST /synthesis?speaker=46 "));
+        let query: serde_json::Value =
+            serde_json::from_str(&requests[3][requests[3].find('{').unwrap()..]).unwrap();
+        assert_eq!(Some(1.3), query["speedScale"].as_f64());
+        assert_eq!(Some(0.05), query["pitchScale"].as_f64());
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    // #[ignore]
+    fn test10() {
+        let mut config = Config::default();
+        config.spoon.should_comment_heart = true;
+        let (mut client, spoon) = new_client(config);
+
+        let mut like = LiveLike::default();
+        like.data.author.nickname = "Mike".to_string();
+        let mut message = LiveMessage::default();
+        message.data.user.id = 30;
+        message.update_component.message.value = "help".to_string();
+        let frames = [
+            r#"{"event":"live_join","type":"live_rpt","live_id":1,"result":{"code":0,"detail":"success"},"appversion":"8.3.3","useragent":"Web"}"#.to_string(),
+            r#"{"event":"live_rank","live_id":1}"#.to_string(),
+            serde_json::to_string(&SpoonEvent::LiveLike(like)).unwrap(),
+            serde_json::to_string(&SpoonEvent::LiveMessage(message)).unwrap(),
+        ];
+        let path = std::env::temp_dir().join("spoon_comment_viewer_client_test10.jsonl");
+        let capture = frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                serde_json::json!({"timestamp": 1_000 * i, "frame": frame}).to_string() + "\n"
+            })
+            .collect::<String>();
+        fs::write(&path, capture).unwrap();
+
+        client.replay(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            vec![
+                "help ではなくスラッシュを先頭に付けて\n/help と打ってみてね。",
+                "Mikeさん、ハートありがとう。",
+            ],
+            spoon.comments()
+        );
+        assert_eq!(1, client.database.select_gifts_by_live(1).unwrap().len());
+        assert_eq!(
+            "help",
+            client.database.select_last_comments(30, 1).unwrap()[0].message
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    // #[ignore]
+    fn test11() {
+        let (mut client, _) = new_client(Config::default());
+
+        //A `live_join` rejected after a reconnection is reported as an error.
+        let mut o = LiveJoin::default();
+        o.result.detail = "fail".to_string();
+        assert!(client.process_event(SpoonEvent::LiveJoin(o)).is_err());
+        let mut o = LiveJoin::default();
+        o.result.detail = "success".to_string();
+        assert!(client.process_event(SpoonEvent::LiveJoin(o)).is_ok());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test12() {
+        let path = std::env::temp_dir().join("spoon_comment_viewer_client_test12.sqlite3");
+        let path = path.to_str().unwrap().to_string();
+        let _ = fs::remove_file(&path);
+        Database::new(Some(&path))
+            .unwrap()
+            .set_tag(7, notes::TAG_BLOCK, "")
+            .unwrap();
+
+        let mut config = Config {
+            database_file: path.clone(),
+            ..Default::default()
+        };
+        config.spoon.should_comment_listener = true;
+        let spoon = Rc::new(FakeBackend::new(1));
+        let mut client = SpoonClient::with_backend(Rc::new(config.clone()), spoon.clone()).unwrap();
+        spoon.set_listeners(vec![Listener {
+            id: 7,
+            nickname: "Troll".to_string(),
+            tag: "troll".to_string(),
+        }]);
+
+        //While the database is locked, the listener is neither greeted nor let in.
+        let other = rusqlite::Connection::open(&path).unwrap();
+        other.execute_batch("BEGIN EXCLUSIVE;").unwrap();
+        client.process_listeners(&config).unwrap();
+        client.flush_comments();
+        assert!(spoon.comments().is_empty());
+        assert!(spoon.blocked_listener_ids().is_empty());
+
+        //retried after the lock is released
+        other.execute_batch("COMMIT;").unwrap();
+        client.process_listeners(&config).unwrap();
+        client.flush_comments();
+        assert!(spoon.comments().is_empty());
+        assert_eq!(vec![7], spoon.blocked_listener_ids());
+
+        drop(client);
+        fs::remove_file(&path).unwrap();
     }
 }
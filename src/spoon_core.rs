@@ -1,18 +1,20 @@
+use std::cell::Cell;
 use std::thread;
 use std::{error::Error, path::Path, rc::Rc, time::Duration};
 
 use itertools::Itertools;
-use log::info;
+use log::{error, info};
 use reqwest::blocking::Client;
-use thirtyfour_sync::error::WebDriverError;
 
+use super::backend::{LiveStats, SpoonBackend};
 use super::listener::{Listener, Listeners};
 use super::selenium::Selenium;
 
+//`SpoonBackend` implemented by operating the web version of Spoon via Selenium
 pub struct Spoon {
     z: Rc<Selenium>,
     http_client: Client,
-    live_id: u64,
+    live_id: Cell<u64>,
 }
 
 impl Spoon {
@@ -23,16 +25,18 @@ impl Spoon {
                 .timeout(Some(api_timeout))
                 .build()
                 .unwrap(),
-            live_id: 0,
+            live_id: Cell::new(0),
         }
     }
+}
 
-    pub fn login(
+impl SpoonBackend for Spoon {
+    fn login(
         &self,
         url: &str,
         twitter_id: &str,
         twitter_password: &str,
-    ) -> Result<(), WebDriverError> {
+    ) -> Result<(), Box<dyn Error>> {
         self.z.get(url)?;
 
         self.z.click("button[title='ログイン / 会員登録']")?;
@@ -49,7 +53,7 @@ impl Spoon {
         Ok(())
     }
 
-    pub fn prepare_live(
+    fn prepare_live(
         &self,
         start_url: &str,
         genre: &str,
@@ -97,11 +101,12 @@ impl Spoon {
         Ok(())
     }
 
-    pub fn start_live(&self) -> Result<(), Box<dyn Error>> {
+    fn start_live(&self) -> Result<(), Box<dyn Error>> {
         self.z.click("button.btn-create").map_err(|e| e.into())
     }
 
-    pub fn update_live_id(&mut self) -> Result<u64, Box<dyn Error>> {
+    fn update_live_id(&self) -> Result<u64, Box<dyn Error>> {
+        #[allow(clippy::result_large_err)] //`WebDriverError` of `thirtyfour_sync`
         let f = || {
             self.z.execute_javascript(
                 "return JSON.parse(window.localStorage.SPOONCAST_liveBroadcastOnair).liveId;",
@@ -122,9 +127,9 @@ impl Spoon {
             if let serde_json::value::Value::Number(n) = live_id_json.unwrap() {
                 match n.as_u64() {
                     Some(id) => {
-                        self.live_id = id;
-                        info!("live_id: {}", self.live_id);
-                        return Ok(self.live_id);
+                        self.live_id.set(id);
+                        info!("live_id: {}", id);
+                        return Ok(id);
                     }
                     None => return Err("Failed to parse the live id as number.".into()),
                 }
@@ -135,8 +140,12 @@ impl Spoon {
         unreachable!();
     }
 
+    fn live_id(&self) -> u64 {
+        self.live_id.get()
+    }
+
     //This method is slow; it takes around 300ms.
-    pub fn post_comment(&self, s: &str) -> Result<(), WebDriverError> {
+    fn post_comment(&self, s: &str) -> Result<(), Box<dyn Error>> {
         //As each comment is truncated to at most 100 characters (in Unicode) in Spoon, we avoid information's being lost by explicitly splitting a comment.
         for mut s in s.chars().chunks(100).into_iter() {
             let s = s.join("");
@@ -146,8 +155,17 @@ impl Spoon {
         Ok(())
     }
 
-    //retrieves the list of the names of current listeners
-    pub fn retrieve_listeners(&self) -> Result<Vec<Listener>, Box<dyn Error>> {
+    fn access_token(&self) -> Result<String, Box<dyn Error>> {
+        match self
+            .z
+            .execute_javascript("return window.localStorage.SPOONCAST_requestBearerToken;")?
+        {
+            serde_json::value::Value::String(s) if (!s.is_empty()) => Ok(s),
+            _ => Err("Failed to retrieve the access token.".into()),
+        }
+    }
+
+    fn retrieve_listeners(&self) -> Result<Vec<Listener>, Box<dyn Error>> {
         let mut ret = Vec::with_capacity(100);
 
         let f = |url: &str| -> Result<Listeners, Box<dyn Error>> {
@@ -159,7 +177,7 @@ impl Spoon {
 
         let mut url = format!(
             "https://jp-api.spooncast.net/lives/{}/listeners/",
-            self.live_id
+            self.live_id.get()
         );
         loop {
             let mut res = f(&url)?;
@@ -172,4 +190,78 @@ impl Spoon {
         }
         Ok(ret)
     }
+
+    //This method is slow; it takes around 50ms.
+    fn retrieve_stats(&self) -> Result<LiveStats, Box<dyn Error>> {
+        let mut timestamp = self
+            .z
+            .inner_text(".time-chip-container span")?
+            .trim()
+            .to_string();
+        if (timestamp.len() == 5) {
+            timestamp = format!("00:{}", timestamp);
+        }
+
+        let count_info_list = self.z.query_all("ul.count-info-list li")?;
+        let mut count_info_list_str = vec![];
+        for e in count_info_list {
+            count_info_list_str.push(e.text()?.trim().to_string());
+        }
+        match count_info_list_str.len() {
+            //followers-only stream (ranking is not shown)
+            4 => {
+                count_info_list_str.insert(0, "?".to_string());
+            }
+            //normal streaming
+            5 => {
+                //do nothing
+            }
+            _ => {
+                error!(
+                    "`count_info_list` is of an unexpected form. Its length is {}.",
+                    count_info_list_str.len()
+                );
+                for _ in 0..(5 - count_info_list_str.len()) {
+                    count_info_list_str.insert(0, "?".to_string());
+                }
+            }
+        }
+
+        Ok(LiveStats {
+            timestamp,
+            ranking: count_info_list_str[0].clone(),
+            num_spoon: count_info_list_str[1].clone(),
+            num_heart: count_info_list_str[2].clone(),
+            num_current_listener: count_info_list_str[3].clone(),
+            num_total_listener: count_info_list_str[4].clone(),
+        })
+    }
+
+    //uses the same API as the block button in the listener list of the web version
+    fn block_listener(&self, listener_id: usize) -> Result<(), Box<dyn Error>> {
+        let res = self
+            .http_client
+            .post(format!(
+                "https://jp-api.spooncast.net/lives/{}/block/",
+                self.live_id.get()
+            ))
+            .bearer_auth(self.access_token()?)
+            .header("Content-Type", "application/json")
+            .body(serde_json::json!({ "block_user_id": listener_id }).to_string())
+            .send()?;
+        if (res.status().is_success()) {
+            Ok(())
+        } else {
+            Err(format!(
+                "failed to block the listener {}: {}",
+                listener_id,
+                res.text()?
+            )
+            .into())
+        }
+    }
+
+    fn close(&self) -> Result<(), Box<dyn Error>> {
+        self.z.close().map_err(|e| e.into())
+    }
 }
@@ -0,0 +1,336 @@
+//`stats` mode of the binary, which prints the statistics over the listener database.
+
+use std::{error::Error, str::FromStr};
+
+use itertools::Itertools;
+use serde_json::{json, Map, Value};
+
+use super::database::{Database, DatabaseError, ListenerEntity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format `{}` (expected `table`, `csv` or `json`)", s).into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub format: Format,
+    pub limit: usize, //the number of the rows in the rankings
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            format: Format::Table,
+            limit: 10,
+        }
+    }
+}
+
+impl Options {
+    //parses the arguments following `stats` (e.g. `--format csv --limit 20`)
+    pub fn parse(args: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut ret = Self::default();
+        let mut it = args.iter();
+        while let Some(arg) = it.next() {
+            let value = it
+                .next()
+                .ok_or_else(|| format!("no value is specified for `{}`", arg))?;
+            match arg.as_str() {
+                "--format" => ret.format = value.parse()?,
+                "--limit" => ret.limit = value.parse()?,
+                _ => return Err(format!("unknown option `{}`", arg).into()),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+//also used by `export` mode
+pub(super) struct Table {
+    pub name: &'static str,
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+fn to_hours(d: std::time::Duration) -> f64 {
+    (d.as_secs() as f64 / 360.0).round() / 10.0
+}
+
+fn tables(database: &Database, limit: usize) -> Result<Vec<Table>, DatabaseError> {
+    let to_rows = |entities: Vec<ListenerEntity>| -> Vec<Vec<Value>> {
+        entities
+            .into_iter()
+            .enumerate()
+            .map(|(i, e)| {
+                vec![
+                    json!(i + 1),
+                    json!(e.id),
+                    json!(e.name),
+                    json!(to_hours(e.stay_duration)),
+                    json!(e.visit_count),
+                ]
+            })
+            .collect()
+    };
+    let top_by_stay = to_rows(database.select_top(limit)?);
+    let top_by_visits = to_rows(database.select_top_by_visit_count(limit)?);
+    let listener_columns = vec!["rank", "id", "name", "stay_hours", "visit_count"];
+
+    let lives = database
+        .select_live_summaries()?
+        .into_iter()
+        .map(|e| {
+            let retention = if (e.num_listener == 0) {
+                0.0
+            } else {
+                (e.num_retained_listener as f64 / e.num_listener as f64 * 1000.0).round() / 10.0
+            };
+            vec![
+                json!(e.id),
+                json!(e.started_at.format("%Y-%m-%d %H:%M").to_string()),
+                json!(e.num_listener),
+                json!(e.num_new_listener),
+                json!(e.num_returning_listener()),
+                json!(retention),
+                json!(e.num_comment),
+                json!(e.num_spoon),
+            ]
+        })
+        .collect();
+
+    let gifts = database
+        .select_gift_totals(limit)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, e)| {
+            vec![
+                json!(i + 1),
+                json!(e.listener_id),
+                json!(e.name),
+                json!(e.num_spoon),
+                json!(e.num_heart),
+                json!(e.num_item),
+            ]
+        })
+        .collect();
+
+    Ok(vec![
+        Table {
+            name: "top_by_stay",
+            columns: listener_columns.clone(),
+            rows: top_by_stay,
+        },
+        Table {
+            name: "top_by_visits",
+            columns: listener_columns,
+            rows: top_by_visits,
+        },
+        Table {
+            name: "lives",
+            columns: vec![
+                "id",
+                "started_at",
+                "listeners",
+                "new",
+                "returning",
+                "retention_percent",
+                "comments",
+                "spoons",
+            ],
+            rows: lives,
+        },
+        Table {
+            name: "top_gifters",
+            columns: vec!["rank", "id", "name", "spoons", "hearts", "items"],
+            rows: gifts,
+        },
+    ])
+}
+
+fn to_string(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+fn render_table(table: &Table) -> String {
+    let cells = [table.columns.iter().map(|s| s.to_string()).collect_vec()]
+        .into_iter()
+        .chain(
+            table
+                .rows
+                .iter()
+                .map(|row| row.iter().map(to_string).collect_vec()),
+        )
+        .collect_vec();
+    let widths = (0..table.columns.len())
+        .map(|i| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap()
+        })
+        .collect_vec();
+    let mut ret = format!("[{}]\n", table.name);
+    for (i, row) in cells.iter().enumerate() {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(s, width)| format!("{}{}", s, " ".repeat(width - s.chars().count())))
+            .join(" | ");
+        ret += line.trim_end();
+        ret += "\n";
+        if (i == 0) {
+            ret += &widths.iter().map(|width| "-".repeat(*width)).join("-+-");
+            ret += "\n";
+        }
+    }
+    ret
+}
+
+fn escape_csv(s: &str) -> String {
+    if (s.contains([',', '"', '\n'])) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn render_csv(table: &Table) -> String {
+    let mut ret = format!("# {}\n{}\n", table.name, table.columns.join(","));
+    for row in &table.rows {
+        ret += &row.iter().map(|v| escape_csv(&to_string(v))).join(",");
+        ret += "\n";
+    }
+    ret
+}
+
+pub(super) fn render(tables: Vec<Table>, format: Format) -> String {
+    match format {
+        Format::Table => tables.iter().map(render_table).join("\n"),
+        Format::Csv => tables.iter().map(render_csv).join("\n"),
+        Format::Json => {
+            let mut ret = Map::new();
+            for table in tables {
+                let rows = table
+                    .rows
+                    .into_iter()
+                    .map(|row| {
+                        Value::Object(
+                            table
+                                .columns
+                                .iter()
+                                .map(|s| s.to_string())
+                                .zip(row)
+                                .collect(),
+                        )
+                    })
+                    .collect();
+                ret.insert(table.name.to_string(), Value::Array(rows));
+            }
+            serde_json::to_string_pretty(&ret).unwrap() + "\n"
+        }
+    }
+}
+
+pub fn report(database: &Database, options: &Options) -> Result<String, DatabaseError> {
+    Ok(render(tables(database, options.limit)?, options.format))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn new_database() -> Database {
+        let db = Database::new(None).unwrap();
+        db.insert(ListenerEntity::new(
+            1,
+            "Mike, Jr.".to_string(),
+            "mike".to_string(),
+            3,
+            Duration::from_secs(5400),
+        ))
+        .unwrap();
+        db.insert(ListenerEntity::new(
+            2,
+            "Lisa".to_string(),
+            "lisa".to_string(),
+            10,
+            Duration::from_secs(3600),
+        ))
+        .unwrap();
+        db
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let args = ["--format", "json", "--limit", "1"].map(|s| s.to_string());
+        assert_eq!(
+            Options {
+                format: Format::Json,
+                limit: 1
+            },
+            Options::parse(&args).unwrap()
+        );
+        assert_eq!(Options::default(), Options::parse(&[]).unwrap());
+        assert!(Options::parse(&args[..1]).is_err());
+        assert!(Options::parse(&["--format".to_string(), "xml".to_string()]).is_err());
+        assert!(Options::parse(&["--verbose".to_string(), "1".to_string()]).is_err());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let db = new_database();
+        let s = report(
+            &db,
+            &Options {
+                format: Format::Csv,
+                limit: 10,
+            },
+        )
+        .unwrap();
+        assert!(s.starts_with(
+            "# top_by_stay\nrank,id,name,stay_hours,visit_count\n1,1,\"Mike, Jr.\",1.5,3\n2,2,Lisa,1.0,10\n\n# top_by_visits\n"
+        ));
+
+        let s = report(
+            &db,
+            &Options {
+                format: Format::Json,
+                limit: 1,
+            },
+        )
+        .unwrap();
+        let v: Value = serde_json::from_str(&s).unwrap();
+        assert_eq!(1, v["top_by_visits"].as_array().unwrap().len());
+        assert_eq!("Lisa", v["top_by_visits"][0]["name"]);
+        assert_eq!(0, v["lives"].as_array().unwrap().len());
+
+        let s = report(&db, &Options::default()).unwrap();
+        assert!(s.starts_with(
+            "[top_by_stay]\nrank | id | name      | stay_hours | visit_count\n-----+----+-"
+        ));
+    }
+}
@@ -0,0 +1,49 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+//a stub HTTP server which returns `responses` in order, recording the requests (the request line and the body)
+pub fn serve(
+    listener: TcpListener,
+    responses: Vec<(u16, String)>,
+) -> thread::JoinHandle<Vec<String>> {
+    thread::spawn(move || {
+        let mut ret = vec![];
+        for (status, body) in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if (line.trim().is_empty()) {
+                    break;
+                }
+                if let Some((k, v)) = line.split_once(':') {
+                    if (k.eq_ignore_ascii_case("content-length")) {
+                        content_length = v.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut request_body = vec![0; content_length];
+            reader.read_exact(&mut request_body).unwrap();
+            ret.push(format!(
+                "{}{}",
+                request_line.trim(),
+                String::from_utf8(request_body).unwrap()
+            ));
+
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+        ret
+    })
+}
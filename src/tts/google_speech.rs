@@ -0,0 +1,57 @@
+use std::{path::Path, process::Command};
+
+use super::super::player::Pitch;
+use super::{cache_path, TtsBackend, TtsError, Voice};
+
+//the `google_speech` command, which is mainly used for English
+pub struct GoogleSpeech {
+    output_dir: String,
+}
+
+impl GoogleSpeech {
+    pub fn new(output_dir: &str) -> Self {
+        Self {
+            output_dir: output_dir.to_string(),
+        }
+    }
+}
+
+impl TtsBackend for GoogleSpeech {
+    fn name(&self) -> &'static str {
+        "google_speech"
+    }
+
+    fn synthesize(&self, text: &str, voice: &Voice) -> Result<String, TtsError> {
+        let lang = voice.locale.code();
+        let filepath = cache_path(&self.output_dir, &format!("{}_{}", lang, text), "mp3");
+        if (Path::new(&filepath).is_file()) {
+            return Ok(filepath);
+        }
+
+        let res = Command::new("google_speech")
+            .args(["--lang", lang, "--output", &filepath, text])
+            .output()
+            .map_err(|e| TtsError::Unavailable(format!("failed to execute: {}", e)))?;
+        if (!res.status.success()) {
+            return Err(TtsError::Failed(format!(
+                "non-zero exit status is returned: {}",
+                String::from_utf8(res.stderr).unwrap_or_default()
+            )));
+        }
+        Ok(filepath)
+    }
+
+    //The output is quieter than the one of VOICEVOX.
+    fn volume(&self) -> f64 {
+        2.
+    }
+
+    //The default voice sounds too low for the bot.
+    fn pitch(&self) -> Pitch {
+        Pitch {
+            base: 150,
+            high: 300,
+            low: -450,
+        }
+    }
+}
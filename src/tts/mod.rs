@@ -0,0 +1,155 @@
+mod google_speech;
+mod silent;
+mod voicevox_engine;
+mod voicevox_web;
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    error::Error,
+    fmt,
+    hash::{Hash, Hasher},
+    time::Duration,
+};
+
+use regex::Regex;
+
+use super::config::{self, TtsEngine, VoiceVoxBackend};
+use super::locale::Locale;
+use super::player::Pitch;
+use super::util;
+
+pub use google_speech::GoogleSpeech;
+pub use silent::Silent;
+pub use voicevox_engine::VoiceVoxEngine;
+pub use voicevox_web::VoiceVoxWeb;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voice {
+    //ignored by the engines other than VOICEVOX
+    pub speaker: usize,
+    pub speed: f64,
+    pub pitch: f64, //also ignored by the web API of VOICEVOX
+    pub locale: Locale,
+}
+
+#[derive(Debug)]
+pub enum TtsError {
+    //only this text failed
+    Failed(String),
+    //The engine should not be called for a while (e.g. `429 Too Many Requests`).
+    RateLimited(Duration),
+    //The engine can no longer be used (e.g. the points of the web API have run out).
+    Unavailable(String),
+}
+
+impl fmt::Display for TtsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TtsError::Failed(s) => write!(f, "{}", s),
+            TtsError::RateLimited(d) => write!(f, "rate limited for {:?}", d),
+            TtsError::Unavailable(s) => write!(f, "unavailable: {}", s),
+        }
+    }
+}
+
+impl Error for TtsError {}
+
+impl From<reqwest::Error> for TtsError {
+    fn from(e: reqwest::Error) -> Self {
+        if (e.is_timeout()) {
+            TtsError::Failed(format!("timed out: {}", e))
+        } else if (e.is_connect()) {
+            TtsError::Failed(format!("failed to connect: {}", e))
+        } else {
+            TtsError::Failed(e.to_string())
+        }
+    }
+}
+
+impl From<std::io::Error> for TtsError {
+    fn from(e: std::io::Error) -> Self {
+        TtsError::Failed(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for TtsError {
+    fn from(e: serde_json::Error) -> Self {
+        TtsError::Failed(e.to_string())
+    }
+}
+
+//a text-to-speech engine
+pub trait TtsBackend {
+    fn name(&self) -> &'static str;
+
+    //synthesizes `text` into an audio file, returning its path
+    //The files are cached, so the same text is synthesized only once.
+    fn synthesize(&self, text: &str, voice: &Voice) -> Result<String, TtsError>;
+
+    //how loud the synthesized audio is played
+    fn volume(&self) -> f64 {
+        1.
+    }
+
+    fn pitch(&self) -> Pitch {
+        Pitch::default()
+    }
+}
+
+pub fn new_backend(engine: TtsEngine, config: &config::VoiceVox) -> Box<dyn TtsBackend> {
+    match engine {
+        TtsEngine::VoiceVox => match config.backend {
+            VoiceVoxBackend::WebApi => Box::new(VoiceVoxWeb::new(config)),
+            VoiceVoxBackend::Engine => Box::new(VoiceVoxEngine::new(config)),
+        },
+        TtsEngine::GoogleSpeech => Box::new(GoogleSpeech::new(&config.output_dir)),
+        TtsEngine::Silent => Box::new(Silent::new(&config.output_dir)),
+    }
+}
+
+//the name of the cache file of the audio synthesized from `key` (which should contain every parameter of the synthesis)
+fn cache_path(output_dir: &str, key: &str, extension: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    format!("{}/{}.{}", output_dir, hasher.finish(), extension)
+}
+
+//a rough guess good enough to choose an engine
+fn detect_locale(text: &str) -> Locale {
+    if (util::is_japanese(text)) {
+        Locale::Ja
+    } else if (Regex::new(r#"[가-힣]"#).unwrap().is_match(text)) {
+        Locale::Ko
+    } else {
+        Locale::En
+    }
+}
+
+//the engine `text` is read aloud with, and the language of `text` (`None` if the language is not routed)
+pub fn route(routes: &HashMap<Locale, TtsEngine>, text: &str) -> Option<(TtsEngine, Locale)> {
+    let locale = detect_locale(text);
+    routes.get(&locale).map(|engine| (*engine, locale))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let routes = HashMap::from([
+            (Locale::Ja, TtsEngine::VoiceVox),
+            (Locale::En, TtsEngine::GoogleSpeech),
+        ]);
+        assert_eq!(
+            Some((TtsEngine::VoiceVox, Locale::Ja)),
+            route(&routes, "ABCコンテスト")
+        );
+        assert_eq!(
+            Some((TtsEngine::GoogleSpeech, Locale::En)),
+            route(&routes, "hello")
+        );
+        assert_eq!(None, route(&routes, "사랑합니다"));
+    }
+}
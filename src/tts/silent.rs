@@ -0,0 +1,84 @@
+use std::{fs, path::Path};
+
+use super::{cache_path, TtsBackend, TtsError, Voice};
+
+const SAMPLE_RATE: u32 = 24000;
+
+//writes a silent WAV file as long as it would take to read the text aloud, without calling any engine
+//This is for testing the pipeline (the routing, the queueing and the playing) offline.
+pub struct Silent {
+    output_dir: String,
+}
+
+impl Silent {
+    pub fn new(output_dir: &str) -> Self {
+        Self {
+            output_dir: output_dir.to_string(),
+        }
+    }
+}
+
+//16-bit mono PCM
+fn silent_wav(num_samples: u32) -> Vec<u8> {
+    let data_size = num_samples * 2;
+    let mut ret = Vec::with_capacity(44 + data_size as usize);
+    ret.extend_from_slice(b"RIFF");
+    ret.extend_from_slice(&(36 + data_size).to_le_bytes());
+    ret.extend_from_slice(b"WAVEfmt ");
+    ret.extend_from_slice(&16u32.to_le_bytes());
+    ret.extend_from_slice(&1u16.to_le_bytes()); //PCM
+    ret.extend_from_slice(&1u16.to_le_bytes()); //mono
+    ret.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    ret.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    ret.extend_from_slice(&2u16.to_le_bytes());
+    ret.extend_from_slice(&16u16.to_le_bytes());
+    ret.extend_from_slice(b"data");
+    ret.extend_from_slice(&data_size.to_le_bytes());
+    ret.resize(44 + data_size as usize, 0);
+    ret
+}
+
+impl TtsBackend for Silent {
+    fn name(&self) -> &'static str {
+        "silent"
+    }
+
+    fn synthesize(&self, text: &str, _: &Voice) -> Result<String, TtsError> {
+        let filepath = cache_path(&self.output_dir, &format!("silent_{}", text), "wav");
+        if (!Path::new(&filepath).is_file()) {
+            //around 0.1 seconds per character
+            let num_samples = SAMPLE_RATE / 10 * text.chars().count() as u32;
+            fs::write(&filepath, silent_wav(num_samples))?;
+        }
+        Ok(filepath)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::locale::Locale;
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let output_dir = std::env::temp_dir().join("spoon_comment_viewer_silent_test01");
+        let _ = fs::remove_dir_all(&output_dir);
+        fs::create_dir(&output_dir).unwrap();
+
+        let backend = Silent::new(output_dir.to_str().unwrap());
+        let voice = Voice {
+            speaker: 0,
+            speed: 1.,
+            pitch: 0.,
+            locale: Locale::Ja,
+        };
+        let filepath = backend.synthesize("こんにちは", &voice).unwrap();
+        let wav = fs::read(&filepath).unwrap();
+        assert_eq!(b"RIFF", &wav[..4]);
+        assert_eq!(44 + 2 * 2400 * 5, wav.len());
+        assert_eq!(filepath, backend.synthesize("こんにちは", &voice).unwrap());
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
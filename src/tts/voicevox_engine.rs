@@ -0,0 +1,248 @@
+use std::{fs, path::Path, time::Duration};
+
+use reqwest::blocking::{Client, Response};
+
+use super::super::config;
+use super::{cache_path, TtsBackend, TtsError, Voice};
+
+//a self-hosted VOICEVOX engine, which also exposes the pitch, the intonation and the volume
+pub struct VoiceVoxEngine {
+    client: Client,
+    config: config::VoiceVox,
+}
+
+impl VoiceVoxEngine {
+    pub fn new(config: &config::VoiceVox) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(Some(Duration::from_secs(config.timeout_sec)))
+                .build()
+                .unwrap(),
+            config: config.clone(),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.config.engine.url.trim_end_matches('/'), path)
+    }
+
+    //the ids of the styles of every speaker installed, which are what `speaker` means in the other endpoints
+    pub fn speaker_ids(&self) -> Result<Vec<usize>, TtsError> {
+        let res = self.client.get(self.url("speakers")).send()?;
+        let speakers: serde_json::Value =
+            serde_json::from_str(&check_status(res, "speakers")?.text()?)?;
+        let ids = speakers
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|speaker| speaker["styles"].as_array())
+            .flatten()
+            .filter_map(|style| style["id"].as_u64())
+            .map(|id| id as usize)
+            .collect::<Vec<_>>();
+        if (ids.is_empty()) {
+            return Err(TtsError::Failed(
+                "`/speakers` returned no speaker".to_string(),
+            ));
+        }
+        Ok(ids)
+    }
+}
+
+fn check_status(res: Response, path: &str) -> Result<Response, TtsError> {
+    if (res.status().is_success()) {
+        Ok(res)
+    } else {
+        Err(TtsError::Failed(format!(
+            "`/{}` returned {}: {}",
+            path,
+            res.status(),
+            res.text().unwrap_or_default()
+        )))
+    }
+}
+
+impl TtsBackend for VoiceVoxEngine {
+    fn name(&self) -> &'static str {
+        "VOICEVOX engine"
+    }
+
+    //the two-step protocol of the engine:
+    // `/audio_query` returns the parameters of the synthesis, which are adjusted and then posted to `/synthesis`
+    fn synthesize(&self, text: &str, voice: &Voice) -> Result<String, TtsError> {
+        let config = &self.config;
+        let filepath = cache_path(
+            &config.output_dir,
+            &format!(
+                "engine_{}_{}_{}_{}_{}_{}",
+                voice.speaker,
+                voice.speed,
+                voice.pitch,
+                config.engine.intonation_scale,
+                config.engine.volume_scale,
+                text
+            ),
+            "wav",
+        );
+        if (Path::new(&filepath).is_file()) {
+            return Ok(filepath);
+        }
+        let speaker = voice.speaker.to_string();
+
+        let res = self
+            .client
+            .post(self.url("audio_query"))
+            .query(&[("text", text), ("speaker", &speaker)])
+            .send()?;
+        let mut query: serde_json::Value =
+            serde_json::from_str(&check_status(res, "audio_query")?.text()?)?;
+        let params = query.as_object_mut().ok_or_else(|| {
+            TtsError::Failed("`/audio_query` returned an unexpected response".to_string())
+        })?;
+        params.insert("speedScale".to_string(), voice.speed.into());
+        params.insert("pitchScale".to_string(), voice.pitch.into());
+        params.insert(
+            "intonationScale".to_string(),
+            config.engine.intonation_scale.into(),
+        );
+        params.insert("volumeScale".to_string(), config.engine.volume_scale.into());
+
+        let res = self
+            .client
+            .post(self.url("synthesis"))
+            .query(&[("speaker", &speaker)])
+            .header("Content-Type", "application/json")
+            .body(query.to_string())
+            .send()?;
+        let body = check_status(res, "synthesis")?.bytes()?;
+        if (body.is_empty()) {
+            return Err(TtsError::Failed(
+                "`/synthesis` returned an empty response".to_string(),
+            ));
+        }
+        fs::write(&filepath, body)?;
+        Ok(filepath)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::super::super::config::VoiceVoxBackend;
+    use super::super::super::locale::Locale;
+    use super::super::super::test_server::serve;
+    use super::*;
+
+    fn engine_config(port: u16, name: &str) -> config::VoiceVox {
+        let output_dir = std::env::temp_dir().join(format!("spoon_comment_viewer_engine_{}", name));
+        let _ = fs::remove_dir_all(&output_dir);
+        fs::create_dir(&output_dir).unwrap();
+        config::VoiceVox {
+            backend: VoiceVoxBackend::Engine,
+            engine: config::Engine {
+                url: format!("http://127.0.0.1:{}/", port),
+                pitch_scale: 0.1,
+                intonation_scale: 1.5,
+                volume_scale: 2.0,
+            },
+            speed: 1.2,
+            output_dir: output_dir.to_str().unwrap().to_string(),
+            timeout_sec: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = engine_config(listener.local_addr().unwrap().port(), "test01");
+        let handle = serve(
+            listener,
+            vec![
+                (200, r#"{"accent_phrases":[],"speedScale":1.0}"#.to_string()),
+                (200, "RIFF".to_string()),
+            ],
+        );
+
+        let engine = VoiceVoxEngine::new(&config);
+        let voice = Voice {
+            speaker: 3,
+            speed: 1.2,
+            pitch: 0.1,
+            locale: Locale::Ja,
+        };
+        let filepath = engine.synthesize("こんにちは", &voice).unwrap();
+        assert_eq!("RIFF", fs::read_to_string(&filepath).unwrap());
+        //cached
+        assert_eq!(filepath, engine.synthesize("こんにちは", &voice).unwrap());
+        fs::remove_dir_all(&config.output_dir).unwrap();
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("POST /audio_query?text=%E3%81%93"));
+        assert!(requests[0].contains("&speaker=3 "));
+        assert!(requests[1].starts_with("POST /synthesis?speaker=3 "));
+        let query: serde_json::Value =
+            serde_json::from_str(&requests[1][requests[1].find('{').unwrap()..]).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "accent_phrases": [],
+                "speedScale": 1.2,
+                "pitchScale": 0.1,
+                "intonationScale": 1.5,
+                "volumeScale": 2.0,
+            }),
+            query
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = engine_config(listener.local_addr().unwrap().port(), "test02");
+        let handle = serve(
+            listener,
+            vec![(422, r#"{"detail":"invalid speaker"}"#.to_string())],
+        );
+
+        let engine = VoiceVoxEngine::new(&config);
+        let voice = Voice {
+            speaker: 9999,
+            speed: 1.,
+            pitch: 0.,
+            locale: Locale::Ja,
+        };
+        let e = engine.synthesize("こんにちは", &voice).unwrap_err();
+        assert!(e.to_string().contains("invalid speaker"));
+        assert_eq!(0, fs::read_dir(&config.output_dir).unwrap().count());
+        fs::remove_dir_all(&config.output_dir).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = engine_config(listener.local_addr().unwrap().port(), "test03");
+        let handle = serve(
+            listener,
+            vec![
+                (
+                    200,
+                    r#"[{"name":"ずんだもん","styles":[{"name":"あまあま","id":1},{"name":"ノーマル","id":3}]},{"name":"小夜/SAYO","styles":[{"name":"ノーマル","id":46}]}]"#.to_string(),
+                ),
+                (200, "[]".to_string()),
+            ],
+        );
+
+        let engine = VoiceVoxEngine::new(&config);
+        assert_eq!(vec![1, 3, 46], engine.speaker_ids().unwrap());
+        assert!(engine.speaker_ids().is_err());
+        fs::remove_dir_all(&config.output_dir).unwrap();
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("GET /speakers "));
+    }
+}
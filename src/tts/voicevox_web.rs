@@ -0,0 +1,103 @@
+use std::{fs, path::Path, time::Duration};
+
+use reqwest::{blocking::Client, StatusCode};
+
+use super::super::config;
+use super::{cache_path, TtsBackend, TtsError, Voice};
+
+//the web API of su-shiki.com, which takes `key`, `speaker`, `speed` and `text` in a single GET request
+pub struct VoiceVoxWeb {
+    client: Client,
+    url: String,
+    api_key: String,
+    output_dir: String,
+}
+
+impl VoiceVoxWeb {
+    pub fn new(config: &config::VoiceVox) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(Some(Duration::from_secs(config.timeout_sec)))
+                .build()
+                .unwrap(),
+            url: config.url.clone(),
+            api_key: config.api_key.clone(),
+            output_dir: config.output_dir.clone(),
+        }
+    }
+}
+
+impl TtsBackend for VoiceVoxWeb {
+    fn name(&self) -> &'static str {
+        "VOICEVOX API"
+    }
+
+    fn synthesize(&self, text: &str, voice: &Voice) -> Result<String, TtsError> {
+        let speaker = voice.speaker.to_string();
+        let speed = voice.speed.to_string();
+        let filepath = cache_path(
+            &self.output_dir,
+            &format!("{}_{}_{}", speaker, speed, text),
+            "wav",
+        );
+        if (Path::new(&filepath).is_file()) {
+            return Ok(filepath);
+        }
+
+        let res = self
+            .client
+            .get(&self.url)
+            .query(&[
+                ("key", self.api_key.as_str()),
+                ("speaker", &speaker),
+                ("speed", &speed),
+                ("text", text),
+            ])
+            .send()?;
+
+        let response_status = res.status();
+        let response_header = res.headers().clone();
+
+        if (!response_status.is_success()) {
+            return Err(match response_status {
+                StatusCode::TOO_MANY_REQUESTS => TtsError::RateLimited(Duration::from_secs(10)),
+                StatusCode::FORBIDDEN => TtsError::Failed(
+                    "`403 Forbidden` is returned. This may be temporary.".to_string(),
+                ),
+                StatusCode::SERVICE_UNAVAILABLE => TtsError::Failed(
+                    "`503 Service Unavailable` is returned. This may be temporary.".to_string(),
+                ),
+                _ => {
+                    let body = res.text().unwrap_or_default();
+                    if (body.contains("notEnoughPoints")) {
+                        TtsError::Unavailable("`notEnoughPoints` is returned.".to_string())
+                    } else if (body.contains(r#""errorMessage": "failed""#)) {
+                        TtsError::Failed(
+                            "`failed` is returned. This is expected to randomly occur.".to_string(),
+                        )
+                    } else {
+                        TtsError::Failed(format!(
+                            "unknown error is returned: {{ status: {}, body: {} }}",
+                            response_status, body
+                        ))
+                    }
+                }
+            });
+        }
+
+        let body = res.bytes().map_err(|e| {
+            TtsError::Failed(format!(
+                "failed to read the response: {} (response header: {:?})",
+                e, response_header
+            ))
+        })?;
+        if (body.is_empty()) {
+            return Err(TtsError::Failed(format!(
+                "the response is unexpectedly empty (response header: {:?})",
+                response_header
+            )));
+        }
+        fs::write(&filepath, body)?;
+        Ok(filepath)
+    }
+}
@@ -3,6 +3,8 @@ use std::{path::Path, time::Duration};
 use log::error;
 use regex::Regex;
 
+use super::locale::Locale;
+
 //tilde expansion + makes it absolute path
 pub fn canonicalize_path(s: &str) -> String {
     let s = s.replace('~', &std::env::var("HOME").unwrap());
@@ -23,20 +25,33 @@ pub fn canonicalize_path_in_place(s: &mut String) {
     *s = canonicalize_path(s);
 }
 
-pub fn pretty_print_duration(d: Duration) -> String {
-    let s = d.as_secs();
-    if (s <= 60) {
-        format!("{}秒", s)
-    } else if (s <= 3600) {
-        let min = s / 60;
-        let sec = s - min * 60;
-        format!("{}分{:02}秒", min, sec)
-    } else {
-        let hour = s / 3600;
-        let min = (s - hour * 3600) / 60;
-        let sec = s - hour * 3600 - min * 60;
-        format!("{}時間{:02}分{:02}秒", hour, min, sec)
+//same as `canonicalize_path()` but for a file created later (e.g. an output file), whose directory should exist
+pub fn canonicalize_new_file_path(s: &str) -> String {
+    let s = s.replace('~', &std::env::var("HOME").unwrap());
+    let path = Path::new(&s);
+    if (path.exists()) {
+        return canonicalize_path(&s);
     }
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(file_name)) => {
+            let dir = if (dir.as_os_str().is_empty()) {
+                Path::new(".")
+            } else {
+                dir
+            };
+            Path::new(&canonicalize_path(dir.to_str().unwrap()))
+                .join(file_name)
+                .to_str()
+                .unwrap()
+                .to_string()
+        }
+        _ => canonicalize_path(&s),
+    }
+}
+
+//See `Locale::pretty_print_duration()` for the other languages.
+pub fn pretty_print_duration(d: Duration) -> String {
+    Locale::Ja.pretty_print_duration(d)
 }
 
 pub fn is_japanese(s: &str) -> bool {
@@ -45,6 +60,7 @@ pub fn is_japanese(s: &str) -> bool {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)] //`assert_eq!(true, ..)` reads better beside `assert_eq!(false, ..)`
 mod tests {
     use super::*;
 
@@ -64,6 +80,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_canonicalize_new_file_path() {
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            cwd.join("capture.jsonl").to_str().unwrap(),
+            canonicalize_new_file_path("capture.jsonl")
+        );
+        assert_eq!(
+            cwd.join("src/capture.jsonl").to_str().unwrap(),
+            canonicalize_new_file_path("./src/capture.jsonl")
+        );
+        assert_eq!(
+            format!("{}/capture.jsonl", std::env::var("HOME").unwrap()),
+            canonicalize_new_file_path("~/capture.jsonl")
+        );
+    }
+
     #[test]
     fn test_is_japanese() {
         assert_eq!(false, is_japanese("hello"));
@@ -1,41 +1,37 @@
 use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
-    fs,
-    hash::{Hash, Hasher},
-    path::Path,
-    process::Command,
+    collections::{HashMap, HashSet},
     sync::mpsc::{self, Receiver, Sender},
     thread,
-    time::Duration,
+    time::Instant,
 };
 
 use log::{error, info};
-use reqwest::{
-    blocking::{Client, Response},
-    StatusCode,
-};
 
-use super::config::Config;
+use super::config::{self, Config, TtsEngine, VoicePreset, VoiceVoxBackend};
 use super::filter::Filter;
+use super::locale::Locale;
 use super::player::Audio;
 use super::player::AudioEffect;
 use super::player::Player;
-use super::util;
+use super::pronunciation::Dictionary;
+use super::tts::{self, TtsBackend, TtsError, Voice, VoiceVoxEngine};
 
 /*-------------------------------------*/
 
 struct APIRequest {
     script: String,
     effect: AudioEffect,
-    speaker: usize,
+    engine: TtsEngine,
+    voice: Voice,
 }
 
 impl APIRequest {
-    fn new(script: &str, effect: AudioEffect, speaker: usize) -> Self {
+    fn new(script: &str, effect: AudioEffect, engine: TtsEngine, voice: Voice) -> Self {
         Self {
             script: script.to_string(),
             effect,
-            speaker,
+            engine,
+            voice,
         }
     }
 }
@@ -53,149 +49,52 @@ fn player_thread(rx: Receiver<Audio>) {
     }
 }
 
-fn calculate_hash(s: &str) -> String {
-    let mut hasher = DefaultHasher::new();
-    s.hash(&mut hasher);
-    hasher.finish().to_string()
-}
-
 fn api_thread(rx: Receiver<APIRequest>, config: Config) {
     let config = config.voicevox;
 
     let (tx, rx2) = mpsc::channel();
     thread::spawn(move || player_thread(rx2));
 
-    let client = Client::builder()
-        .timeout(Some(Duration::from_secs(config.timeout_sec)))
-        .build()
-        .unwrap();
+    //created on the first use
+    let mut backends: HashMap<TtsEngine, Box<dyn TtsBackend>> = HashMap::new();
+    let mut unavailable_engines = HashSet::new();
+    //The requests to a rate-limited engine are discarded until it resumes, while the other engines keep working.
+    let mut suspended_until: HashMap<TtsEngine, Instant> = HashMap::new();
 
     loop {
         let req: APIRequest = match rx.recv() {
             Err(_) => return,
             Ok(r) => r,
         };
+        if (unavailable_engines.contains(&req.engine)) {
+            continue;
+        }
+        if (suspended_until
+            .get(&req.engine)
+            .is_some_and(|t| Instant::now() < *t))
+        {
+            continue;
+        }
 
-        //for English
-        if (req.effect.pitch_for_english) {
-            let filepath = format!("{}/{}.mp3", config.output_dir, calculate_hash(&req.script));
-
-            if (!Path::new(&filepath).is_file()) {
-                let res = match Command::new("google_speech")
-                    .args(["--output", &filepath, &req.script])
-                    .output()
-                {
-                    Ok(r) => r,
-                    Err(e) => {
-                        error!("Failed to execute `google_speech`: {}", e);
-                        continue;
-                    }
-                };
-
-                if (!res.status.success()) {
-                    error!(
-                        "Non-zero exit status is returned from `google_speech`: {}",
-                        String::from_utf8(res.stderr).unwrap_or_default()
-                    );
-                    continue;
-                }
+        let backend = backends
+            .entry(req.engine)
+            .or_insert_with(|| tts::new_backend(req.engine, &config));
+        match backend.synthesize(&req.script, &req.voice) {
+            Ok(filepath) => {
+                let audio =
+                    Audio::new(&filepath, backend.volume(), req.effect).with_pitch(backend.pitch());
+                tx.send(audio).unwrap();
             }
-
-            let audio = Audio::new(&filepath, 2., req.effect);
-            tx.send(audio).unwrap();
-
-        //for Japanese
-        } else {
-            let mut params = HashMap::new();
-            let speaker = req.speaker.to_string();
-            let speed = config.speed.to_string();
-            params.insert("key", &config.api_key);
-            params.insert("speaker", &speaker);
-            params.insert("speed", &speed);
-            params.insert("text", &req.script);
-
-            let filepath = format!(
-                "{}/{}.wav",
-                config.output_dir,
-                calculate_hash(&format!("{}_{}_{}", speaker, speed, req.script))
-            );
-            if (!Path::new(&filepath).is_file()) {
-                let res: Response = match client.get(&config.url).query(&params).send() {
-                    Err(e) => {
-                        if (e.is_timeout()) {
-                            error!("VOICEVOX API timed out.");
-                        } else if (e.is_connect()) {
-                            error!("Failed to connect to VOICEVOX API.");
-                        } else {
-                            error!("Failed to send the request: {}", e);
-                        }
-                        continue;
-                    }
-                    Ok(r) => r,
-                };
-
-                let response_status = res.status();
-                let response_header = res.headers().clone();
-
-                if (!response_status.is_success()) {
-                    match response_status {
-                        StatusCode::TOO_MANY_REQUESTS => {
-                            error!("`429 Too Many Requests` is returned from VOICEVOX API. Suspended for 10 seconds.");
-                            thread::sleep(Duration::from_millis(10000));
-                            while (rx.try_recv().is_ok()) {
-                                //discards
-                            }
-                        }
-                        StatusCode::FORBIDDEN => {
-                            error!(
-                            "`403 Forbidden` is returned from VOICEVOX API. This may be temporary."
-                        );
-                        }
-                        StatusCode::SERVICE_UNAVAILABLE => {
-                            error!(
-                            "`503 Service Unavailable` is returned from VOICEVOX API. This may be temporary."
-                        );
-                        }
-                        _ => {
-                            let body = res.text().unwrap_or_default();
-                            if (body.contains("notEnoughPoints")) {
-                                error!("`notEnoughPoints` is returned from VOICEVOX API. Thread terminated.");
-                                return;
-                            } else if (body.contains(r#""errorMessage": "failed""#)) {
-                                error!("`failed` is returned from VOICEVOX API. This is expected to randomly occur.");
-                            } else {
-                                error!("Unknown error is returned from VOICEVOX API: {{ status: {}, body: {} }}", response_status, body);
-                            }
-                        }
-                    }
-                    continue;
-                }
-
-                let body = match res.bytes() {
-                    Ok(r) => {
-                        if (r.is_empty()) {
-                            error!("Response from VOICEVOX API is unexpectedly empty.");
-                            error!("response header: {:?}", response_header);
-                            continue;
-                        } else {
-                            r
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to read response from VOICEVOX API: {}", e);
-                        error!("response header: {:?}", response_header);
-                        continue;
-                    }
-                };
-
-                if let Err(e) = fs::write(&filepath, body) {
-                    error!("Failed to write to the file [ {} ]: {}", filepath, e);
-                    continue;
-                }
+            Err(TtsError::Failed(e)) => error!("{}: {}", backend.name(), e),
+            Err(TtsError::RateLimited(d)) => {
+                error!("{} is rate limited. Suspended for {:?}.", backend.name(), d);
+                suspended_until.insert(req.engine, Instant::now() + d);
+            }
+            //The other engines keep working.
+            Err(TtsError::Unavailable(e)) => {
+                error!("{} is disabled: {}", backend.name(), e);
+                unavailable_engines.insert(req.engine);
             }
-
-            let audio = Audio::new(&filepath, 1., req.effect);
-            tx.send(audio).unwrap();
         }
     }
 }
@@ -207,6 +106,8 @@ pub struct Script {
     pub script: String,
     pub effect: AudioEffect,
     pub speaker: usize,
+    pub speed: Option<f64>, //`config.voicevox.speed` is used when `None`
+    pub pitch: Option<f64>, //`config.voicevox.engine.pitch_scale` is used when `None`
 }
 
 impl Script {
@@ -215,46 +116,100 @@ impl Script {
             script: script.to_string(),
             effect,
             speaker,
+            speed: None,
+            pitch: None,
+        }
+    }
+
+    //The default effects of the preset are not applied here (see `command::modifier`).
+    pub fn with_preset(self, preset: &VoicePreset) -> Self {
+        Self {
+            speaker: preset.speaker,
+            speed: preset.speed,
+            pitch: preset.pitch,
+            ..self
+        }
+    }
+}
+
+//the voices whose speaker is not provided by the engine (e.g. by a typo or a voice library not installed)
+fn unknown_speakers<'a>(config: &'a config::VoiceVox, ids: &[usize]) -> Vec<(&'a str, usize)> {
+    [("(default)", config.speaker)]
+        .into_iter()
+        .chain(
+            config
+                .voices
+                .iter()
+                .map(|preset| (preset.name.as_str(), preset.speaker)),
+        )
+        .filter(|(_, speaker)| !ids.contains(speaker))
+        .collect()
+}
+
+//checks the speakers against `/speakers` of the engine (The web API has no such endpoint.)
+fn validate_speakers(config: &config::VoiceVox) {
+    match VoiceVoxEngine::new(config).speaker_ids() {
+        Err(e) => error!(
+            "Failed to retrieve the speakers from VOICEVOX engine: {}",
+            e
+        ),
+        Ok(ids) => {
+            for (name, speaker) in unknown_speakers(config, &ids) {
+                error!(
+                    "The speaker {} of the voice `{}` is not found in VOICEVOX engine.",
+                    speaker, name
+                );
+            }
         }
     }
 }
 
+//reads texts aloud, each with the engine chosen by its language (see `config.voicevox.routes`)
 pub struct VoiceVox {
     enabled: bool,
-    should_skip_non_japanese: bool,
-    should_use_google_speech_for_non_japanese: bool,
+    routes: HashMap<Locale, TtsEngine>,
+    speed: f64,
+    pitch: f64,
+    dictionary: Dictionary,
     tx: Option<Sender<APIRequest>>,
     filter: Option<Filter>,
 }
 
 impl VoiceVox {
-    pub fn new(config: &Config, filter: Filter) -> Self {
+    pub fn new(config: &Config, filter: Filter, dictionary: Dictionary) -> Self {
         if (config.voicevox.enabled) {
-            let should_skip_non_japanese = config.voicevox.should_skip_non_japanese;
-            let should_use_google_speech_for_non_japanese =
-                config.voicevox.should_use_google_speech_for_non_japanese;
+            if (config.voicevox.backend == VoiceVoxBackend::Engine) {
+                validate_speakers(&config.voicevox);
+            }
+            let routes = config.voicevox.routes.clone();
+            let speed = config.voicevox.speed;
+            let pitch = config.voicevox.engine.pitch_scale;
             let (tx, rx) = mpsc::channel();
             let config = config.clone();
             thread::spawn(move || api_thread(rx, config));
             Self {
                 enabled: true,
-                should_skip_non_japanese,
-                should_use_google_speech_for_non_japanese,
+                routes,
+                speed,
+                pitch,
+                dictionary,
                 tx: Some(tx),
                 filter: Some(filter),
             }
         } else {
             Self {
                 enabled: false,
-                should_skip_non_japanese: false,
-                should_use_google_speech_for_non_japanese: false,
+                routes: HashMap::new(),
+                speed: 0.,
+                pitch: 0.,
+                dictionary,
                 tx: None,
                 filter: None,
             }
         }
     }
 
-    pub fn say(&mut self, mut script: Script) {
+    pub fn say(&mut self, script: Script) {
         if (!self.enabled) {
             return;
         }
@@ -262,19 +217,115 @@ impl VoiceVox {
             info!("Forbidden word detected: [{}]", script.script);
             return;
         }
-        if (self.should_skip_non_japanese && !util::is_japanese(&script.script)) {
-            if (self.should_use_google_speech_for_non_japanese) {
-                script.effect.pitch_for_english = true;
-            } else {
-                return;
-            }
+        let text = self.dictionary.apply(&script.script);
+        if (text.is_empty()) {
+            return;
         }
-        let req = APIRequest::new(&script.script, script.effect, script.speaker);
+        let (engine, locale) = match tts::route(&self.routes, &text) {
+            None => return,
+            Some(r) => r,
+        };
+        let voice = Voice {
+            speaker: script.speaker,
+            speed: script.speed.unwrap_or(self.speed),
+            pitch: script.pitch.unwrap_or(self.pitch),
+            locale,
+        };
+        let req = APIRequest::new(&text, script.effect, engine, voice);
         if let Err(e) = self.tx.as_ref().unwrap().send(req) {
             error!("{}", e);
             self.enabled = false;
         }
     }
+
+    pub fn set_dictionary(&mut self, dictionary: Dictionary) {
+        self.dictionary = dictionary;
+    }
 }
 
 /*-------------------------------------*/
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, net::TcpListener, time::Duration};
+
+    use super::super::test_server::serve;
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let preset = |name: &str, speaker: usize| VoicePreset {
+            name: name.to_string(),
+            speaker,
+            ..Default::default()
+        };
+        let config = config::VoiceVox {
+            speaker: 3,
+            voices: vec![preset("zundamon", 3), preset("typo", 300)],
+            ..Default::default()
+        };
+        assert_eq!(vec![("typo", 300)], unknown_speakers(&config, &[1, 3]));
+        assert_eq!(
+            vec![("(default)", 3), ("zundamon", 3), ("typo", 300)],
+            unknown_speakers(&config, &[])
+        );
+
+        let script = Script::new("hello", AudioEffect::default(), 3).with_preset(&VoicePreset {
+            name: "sayo".to_string(),
+            speaker: 46,
+            speed: Some(1.2),
+            pitch: None,
+            effects: vec![],
+        });
+        assert_eq!(
+            (46, Some(1.2), None),
+            (script.speaker, script.speed, script.pitch)
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://127.0.0.1:{}/",
+            listener.local_addr().unwrap().port()
+        );
+        let handle = serve(listener, vec![(429, "".to_string())]);
+        let output_dir = std::env::temp_dir().join("spoon_comment_viewer_voicevox_test02");
+        let _ = fs::remove_dir_all(&output_dir);
+        fs::create_dir(&output_dir).unwrap();
+
+        let mut config = Config::default();
+        config.voicevox = config::VoiceVox {
+            enabled: true,
+            backend: VoiceVoxBackend::WebApi,
+            routes: HashMap::from([
+                (Locale::Ja, TtsEngine::VoiceVox),
+                (Locale::En, TtsEngine::Silent),
+            ]),
+            url,
+            output_dir: output_dir.to_str().unwrap().to_string(),
+            timeout_sec: 10,
+            ..Default::default()
+        };
+        let mut voicevox = VoiceVox::new(&config, Filter::new(&[]), Dictionary::new(vec![]));
+        let say = |voicevox: &mut VoiceVox, s: &str| {
+            voicevox.say(Script::new(s, AudioEffect::default(), 3));
+        };
+
+        //VOICEVOX is suspended, so the second request is discarded without being sent.
+        say(&mut voicevox, "こんにちは");
+        assert_eq!(1, handle.join().unwrap().len());
+        say(&mut voicevox, "こんばんは");
+        //The other engine keeps working.
+        say(&mut voicevox, "hello");
+        let start = Instant::now();
+        while (fs::read_dir(&output_dir).unwrap().count() == 0) {
+            assert!(start.elapsed() < Duration::from_secs(3));
+            thread::sleep(Duration::from_millis(10));
+        }
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
@@ -0,0 +1,9 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    //The connection was lost and has been re-established.
+    Reconnected { downtime: Duration, num_retry: u32 },
+    //round-trip time measured by a ping/pong exchange
+    Latency(Duration),
+}
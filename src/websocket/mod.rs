@@ -1,5 +1,9 @@
+mod connection_event;
+mod recorder;
+mod replayer;
 mod spoon_websocket;
 #[allow(clippy::module_inception)]
 mod websocket;
 
+pub use connection_event::ConnectionEvent;
 pub use spoon_websocket::WebSocket;
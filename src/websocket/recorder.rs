@@ -0,0 +1,44 @@
+use std::{
+    error::Error,
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+//a line of a capture file
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CapturedFrame {
+    pub timestamp: i64, //UNIX time in milliseconds
+    pub frame: String,  //raw WebSocket frame
+}
+
+//tees raw WebSocket frames into a capture file in the JSON Lines format
+//This is cheaply cloneable and every clone appends to the same file.
+#[derive(Clone)]
+pub struct Recorder {
+    file: Arc<Mutex<File>>,
+}
+
+impl Recorder {
+    pub fn new(path: &str) -> Result<Self, Box<dyn Error>> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    pub fn record(&self, frame: &str) -> Result<(), Box<dyn Error>> {
+        let line = serde_json::to_string(&CapturedFrame {
+            timestamp: Local::now().timestamp_millis(),
+            frame: frame.to_string(),
+        })?;
+        //We flush every line so that a capture survives even if the bot crashes.
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", line)?;
+        file.flush()?;
+        Ok(())
+    }
+}
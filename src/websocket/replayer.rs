@@ -0,0 +1,86 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufRead, BufReader},
+    sync::mpsc::Sender,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use log::info;
+
+use super::recorder::CapturedFrame;
+
+//feeds the frames in a capture file (written by `Recorder`) to `tx`, keeping the original intervals
+//`speed` is a multiplier: `1.0` replays at the original speed, `10.0` ten times faster.
+//When `speed` is not positive, all of the frames are sent without waiting.
+//The returned thread finishes when all of the frames have been sent.
+pub fn replay(
+    tx: Sender<String>,
+    path: &str,
+    speed: f64,
+) -> Result<JoinHandle<()>, Box<dyn Error>> {
+    let mut frames = vec![];
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if (line.trim().is_empty()) {
+            continue;
+        }
+        frames.push(serde_json::from_str::<CapturedFrame>(&line)?);
+    }
+
+    Ok(thread::spawn(move || {
+        let mut previous_timestamp = frames.first().map(|f| f.timestamp).unwrap_or_default();
+        for f in frames {
+            if (speed > 0.) {
+                let interval = (f.timestamp - previous_timestamp).max(0) as f64 / speed;
+                thread::sleep(Duration::from_millis(interval as u64));
+            }
+            previous_timestamp = f.timestamp;
+            if (tx.send(f.frame).is_err()) {
+                return;
+            }
+        }
+        info!("Replay finished.");
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::mpsc,
+        time::{Duration, Instant},
+    };
+
+    use super::super::recorder::Recorder;
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let path = std::env::temp_dir().join("spoon_comment_viewer_replayer_test01.jsonl");
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+
+        let recorder = Recorder::new(path).unwrap();
+        recorder.record(r#"{"event":"live_join"}"#).unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        recorder.record("hello\nworld").unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let start = Instant::now();
+        replay(tx.clone(), path, 1.).unwrap();
+        assert_eq!(r#"{"event":"live_join"}"#, rx.recv().unwrap());
+        assert_eq!("hello\nworld", rx.recv().unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        let start = Instant::now();
+        replay(tx, path, 0.).unwrap();
+        assert_eq!(r#"{"event":"live_join"}"#, rx.recv().unwrap());
+        assert_eq!("hello\nworld", rx.recv().unwrap());
+        assert!(start.elapsed() < Duration::from_millis(200));
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,47 +1,232 @@
 use std::{
     error::Error,
-    sync::mpsc::{self, Receiver},
-    thread,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
-use super::websocket;
+use itertools::Itertools;
+use log::{error, info};
+
+use super::connection_event::ConnectionEvent;
+use super::recorder::Recorder;
+use super::replayer;
+use super::websocket::{self, Heartbeat, Outgoing};
+
+//how long `post_comment()` waits for a frame to be sent before giving up (e.g. while reconnecting)
+const SEND_TIMEOUT: Duration = Duration::from_secs(1);
+
+//exponential backoff: `initial`, `initial * 2`, `initial * 4`, ... (at most `max`)
+fn backoff(num_retry: u32, initial: Duration, max: Duration) -> Duration {
+    let factor = 2u32.saturating_pow(num_retry.saturating_sub(1));
+    initial.saturating_mul(factor).min(max)
+}
+
+fn live_join_message(live_id: u64, num_retry: u32) -> String {
+    format!(
+        r#"
+            {{
+                "live_id":    "{}",
+                "appversion": "8.3.3",
+                "retry":      {},
+                "reconnect":  true,
+                "event":      "live_join",
+                "type":       "live_req",
+                "useragent":  "Web"
+            }}
+        "#,
+        live_id, num_retry
+    )
+}
+
+//application-level keepalive which the official web client periodically sends
+fn live_health_message(live_id: u64) -> String {
+    format!(
+        r#"
+            {{
+                "live_id":    "{}",
+                "appversion": "8.3.3",
+                "retry":      0,
+                "reconnect":  false,
+                "event":      "live_health",
+                "type":       "live_rpt",
+                "useragent":  "Web"
+            }}
+        "#,
+        live_id
+    )
+}
+
+fn live_message_message(live_id: u64, token: &str, message: &str) -> String {
+    serde_json::json!({
+        "live_id": live_id.to_string(),
+        "appversion": "8.3.3",
+        "useragent": "Web",
+        "token": format!("Bearer {}", token),
+        "message": message,
+        "event": "live_message",
+        "type": "live_rpt",
+    })
+    .to_string()
+}
+
+//everything needed to (re)establish a connection
+struct Connector {
+    url: String,
+    live_id: u64,
+    tx: Sender<String>,
+    status_tx: Sender<ConnectionEvent>,
+    recorder: Option<Recorder>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    heartbeat_interval: Option<Duration>,
+}
+
+impl Connector {
+    fn connect(&self, num_retry: u32) -> Result<websocket::WebSocket, Box<dyn Error>> {
+        websocket::WebSocket::new(
+            self.tx.clone(),
+            self.status_tx.clone(),
+            &self.url,
+            Some(&live_join_message(self.live_id, num_retry)),
+            self.recorder.clone(),
+            self.heartbeat_interval.map(|interval| Heartbeat {
+                interval,
+                message: Some(live_health_message(self.live_id)),
+            }),
+        )
+    }
+}
+
+//sleeps for `delay`, rejecting the frames posted in the meantime as there is no connection
+//This returns `false` as soon as `WebSocket` is dropped.
+fn wait_unless_dropped(outgoing: &Receiver<Outgoing>, delay: Duration) -> bool {
+    let deadline = Instant::now() + delay;
+    loop {
+        match outgoing.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(o) => {
+                let _ = o.result_tx.send(Err("reconnecting".to_string()));
+            }
+            Err(RecvTimeoutError::Timeout) => return true,
+            Err(RecvTimeoutError::Disconnected) => return false,
+        }
+    }
+}
+
+//keeps reading from `ws`, reconnecting with exponential backoff each time the connection is lost
+//This returns only when `WebSocket` has been dropped.
+fn connection_thread(
+    mut ws: websocket::WebSocket,
+    connector: Connector,
+    outgoing: Receiver<Outgoing>,
+) {
+    loop {
+        if let Err(e) = ws.read_loop(&outgoing) {
+            error!("WebSocket connection lost: {}", e);
+        } else {
+            info!("WebSocket connection finished.");
+            return;
+        }
+
+        let disconnected_at = Instant::now();
+        let mut num_retry = 0;
+        ws = loop {
+            num_retry += 1;
+            let delay = backoff(num_retry, connector.initial_backoff, connector.max_backoff);
+            if (!wait_unless_dropped(&outgoing, delay)) {
+                info!("WebSocket reconnection cancelled.");
+                return;
+            }
+            info!("Reconnecting to WebSocket (retry: {})...", num_retry);
+            match connector.connect(num_retry) {
+                Ok(ws) => break ws,
+                Err(e) => error!("Failed to reconnect to WebSocket: {}", e),
+            }
+        };
+
+        let event = ConnectionEvent::Reconnected {
+            downtime: disconnected_at.elapsed(),
+            num_retry,
+        };
+        if (connector.status_tx.send(event).is_err()) {
+            return;
+        }
+    }
+}
 
 pub struct WebSocket {
+    live_id: u64,
     rx: Option<Receiver<String>>,
+    outgoing_tx: Option<Sender<Outgoing>>,
+    status_rx: Option<Receiver<ConnectionEvent>>,
+    recorder: Option<Recorder>,
+    replayer: Option<JoinHandle<()>>,
 }
 
 impl WebSocket {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        Self { rx: None }
+        Self {
+            live_id: 0,
+            rx: None,
+            outgoing_tx: None,
+            status_rx: None,
+            recorder: None,
+            replayer: None,
+        }
     }
 
-    pub fn connect(&mut self, live_id: u64) -> Result<(), Box<dyn Error>> {
-        let url = format!("wss://jp-hala.spooncast.net/{}", live_id);
+    //tees every frame received after `connect()` into the capture file `capture_file`
+    pub fn record(&mut self, capture_file: &str) -> Result<(), Box<dyn Error>> {
+        self.recorder = Some(Recorder::new(capture_file)?);
+        Ok(())
+    }
 
+    //feeds the frames in the capture file `capture_file` instead of connecting to Spoon
+    //See `replayer::replay()` for the meaning of `speed`.
+    pub fn replay(&mut self, capture_file: &str, speed: f64) -> Result<(), Box<dyn Error>> {
         let (tx, rx) = mpsc::channel();
+        self.replayer = Some(replayer::replay(tx, capture_file, speed)?);
+        self.rx = Some(rx);
+        Ok(())
+    }
 
-        let on_open_message = format!(
-            r#"
-                {{
-                    "live_id":    "{}",
-                    "appversion": "8.3.3",
-                    "retry":      0,
-                    "reconnect":  true,
-                    "event":      "live_join",
-                    "type":       "live_req",
-                    "useragent":  "Web"
-                }}
-            "#,
-            live_id
-        );
-
-        let mut ws = websocket::WebSocket::new(tx, &url, Some(&on_open_message))?;
-        thread::spawn(move || {
-            let _ = ws.read_loop();
-        });
+    //whether `replay()` has frames which are not fed yet
+    pub fn is_replaying(&self) -> bool {
+        self.replayer
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
 
+    //`heartbeat_interval`: how often pings and keepalives are sent (`None` to disable)
+    pub fn connect(
+        &mut self,
+        live_id: u64,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+        heartbeat_interval: Option<Duration>,
+    ) -> Result<(), Box<dyn Error>> {
+        let (tx, rx) = mpsc::channel();
+        let (status_tx, status_rx) = mpsc::channel();
+
+        let connector = Connector {
+            url: format!("wss://jp-hala.spooncast.net/{}", live_id),
+            live_id,
+            tx,
+            status_tx,
+            recorder: self.recorder.clone(),
+            initial_backoff,
+            max_backoff,
+            heartbeat_interval,
+        };
+        let ws = connector.connect(0)?;
+        let (outgoing_tx, outgoing_rx) = mpsc::channel();
+        thread::spawn(move || connection_thread(ws, connector, outgoing_rx));
+
+        self.live_id = live_id;
         self.rx = Some(rx);
+        self.outgoing_tx = Some(outgoing_tx);
+        self.status_rx = Some(status_rx);
         Ok(())
     }
 
@@ -52,4 +237,196 @@ impl WebSocket {
         }
         ret
     }
+
+    //posts a comment as the user identified by the access token `token`
+    //This is much faster than typing into the browser, and returns an error if the comment is not sent within `SEND_TIMEOUT` (e.g. while reconnecting).
+    pub fn post_comment(&self, token: &str, s: &str) -> Result<(), Box<dyn Error>> {
+        let outgoing_tx = match &self.outgoing_tx {
+            None => return Err("WebSocket is not connected".into()),
+            Some(tx) => tx,
+        };
+        //As each comment is truncated to at most 100 characters (in Unicode) in Spoon, we avoid information's being lost by explicitly splitting a comment.
+        for mut s in s.chars().chunks(100).into_iter() {
+            let s = s.join("");
+            let (result_tx, result_rx) = mpsc::channel();
+            outgoing_tx.send(Outgoing {
+                frame: live_message_message(self.live_id, token, &s),
+                result_tx,
+                deadline: Instant::now() + SEND_TIMEOUT,
+            })?;
+            match result_rx.recv_timeout(SEND_TIMEOUT) {
+                Ok(result) => result?,
+                Err(_) => return Err("timed out (the connection may be lost)".into()),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn fetch_connection_events(&self) -> Vec<ConnectionEvent> {
+        match &self.status_rx {
+            None => vec![],
+            Some(rx) => rx.try_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let initial = Duration::from_millis(500);
+        let max = Duration::from_secs(10);
+        assert_eq!(Duration::from_millis(500), backoff(1, initial, max));
+        assert_eq!(Duration::from_millis(1000), backoff(2, initial, max));
+        assert_eq!(Duration::from_millis(2000), backoff(3, initial, max));
+        assert_eq!(Duration::from_millis(8000), backoff(5, initial, max));
+        assert_eq!(Duration::from_secs(10), backoff(6, initial, max));
+        assert_eq!(Duration::from_secs(10), backoff(100, initial, max));
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let s = live_message_message(12, "abc", "\"hello\"\nworld");
+        let v: serde_json::Value = serde_json::from_str(&s).unwrap();
+        assert_eq!("12", v["live_id"]);
+        assert_eq!("Bearer abc", v["token"]);
+        assert_eq!("\"hello\"\nworld", v["message"]);
+        assert_eq!("live_message", v["event"]);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        //a server which closes the first connection right after `live_join`, and then sends a frame over the second one
+        let (server_tx, server_rx) = mpsc::channel();
+        thread::spawn(move || {
+            for frame in [None, Some("after reconnection")] {
+                let mut socket = tungstenite::accept(listener.accept().unwrap().0).unwrap();
+                let join: serde_json::Value =
+                    serde_json::from_str(&socket.read_message().unwrap().into_text().unwrap())
+                        .unwrap();
+                server_tx.send(join["retry"].as_u64().unwrap()).unwrap();
+                match frame {
+                    None => socket.close(None).unwrap(),
+                    Some(s) => socket.write_message(tungstenite::Message::text(s)).unwrap(),
+                }
+                //waits for the close handshake or the client's shutdown
+                while (socket.read_message().is_ok()) {}
+            }
+        });
+
+        let (tx, rx) = mpsc::channel();
+        let (status_tx, status_rx) = mpsc::channel();
+        let connector = Connector {
+            url,
+            live_id: 12,
+            tx,
+            status_tx,
+            recorder: None,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+            heartbeat_interval: None,
+        };
+        let ws = connector.connect(0).unwrap();
+        let (outgoing_tx, outgoing_rx) = mpsc::channel();
+        let handle = thread::spawn(move || connection_thread(ws, connector, outgoing_rx));
+
+        let timeout = Duration::from_secs(3);
+        assert_eq!(0, server_rx.recv_timeout(timeout).unwrap());
+        assert_eq!(1, server_rx.recv_timeout(timeout).unwrap());
+        assert_eq!("after reconnection", rx.recv_timeout(timeout).unwrap());
+        match status_rx.recv_timeout(timeout).unwrap() {
+            ConnectionEvent::Reconnected { num_retry, .. } => assert_eq!(1, num_retry),
+            e => panic!("unexpected event: {:?}", e),
+        }
+
+        //The thread finishes only when the client shuts down.
+        drop(outgoing_tx);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    // #[ignore]
+    fn test04() {
+        let mut ws = WebSocket::new();
+        assert!(ws.post_comment("abc", "hello").is_err());
+
+        let (outgoing_tx, outgoing_rx) = mpsc::channel();
+        ws.outgoing_tx = Some(outgoing_tx);
+        //Nobody sends the frame, as when reconnecting.
+        assert!(ws.post_comment("abc", "hello").is_err());
+
+        thread::spawn(move || {
+            for o in outgoing_rx {
+                let result = if (Instant::now() > o.deadline) {
+                    Err("expired".to_string())
+                } else {
+                    Ok(())
+                };
+                let _ = o.result_tx.send(result);
+            }
+        });
+        assert!(ws.post_comment("abc", "hello").is_ok());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test05() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        //a server which closes the only connection it accepts, so that the client keeps retrying
+        thread::spawn(move || {
+            let mut socket = tungstenite::accept(listener.accept().unwrap().0).unwrap();
+            socket.read_message().unwrap();
+            socket.close(None).unwrap();
+            while (socket.read_message().is_ok()) {}
+        });
+
+        let (tx, _rx) = mpsc::channel();
+        let (status_tx, _status_rx) = mpsc::channel();
+        let connector = Connector {
+            url,
+            live_id: 12,
+            tx,
+            status_tx,
+            recorder: None,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(60),
+            heartbeat_interval: None,
+        };
+        let ws = connector.connect(0).unwrap();
+        let (outgoing_tx, outgoing_rx) = mpsc::channel();
+        let (finished_tx, finished_rx) = mpsc::channel();
+        thread::spawn(move || {
+            connection_thread(ws, connector, outgoing_rx);
+            finished_tx.send(()).unwrap();
+        });
+
+        //A frame posted during the outage is rejected right away.
+        thread::sleep(Duration::from_millis(500));
+        let (result_tx, result_rx) = mpsc::channel();
+        outgoing_tx
+            .send(Outgoing {
+                frame: "hello".to_string(),
+                result_tx,
+                deadline: Instant::now() + Duration::from_secs(3),
+            })
+            .unwrap();
+        assert!(result_rx
+            .recv_timeout(Duration::from_secs(1))
+            .unwrap()
+            .is_err());
+
+        //The client shuts down in the middle of the backoff.
+        drop(outgoing_tx);
+        finished_rx.recv_timeout(Duration::from_secs(3)).unwrap();
+    }
 }
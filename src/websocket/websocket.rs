@@ -1,16 +1,51 @@
-use std::{error::Error, net::TcpStream, sync::mpsc::Sender};
+use std::{
+    error::Error,
+    io,
+    net::TcpStream,
+    sync::mpsc::{Receiver, Sender, TryRecvError},
+    time::{Duration, Instant},
+};
+
+use log::error;
 use tungstenite::{stream::MaybeTlsStream, Message};
 
+use super::connection_event::ConnectionEvent;
+use super::recorder::Recorder;
+
+//how often `read_loop()` wakes up to send outgoing messages and heartbeats
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+//a frame to be sent, whose result is reported back to the sender
+pub struct Outgoing {
+    pub frame: String,
+    pub result_tx: Sender<Result<(), String>>,
+    //The frame is discarded after this, as the sender has given up on it (e.g. while reconnecting).
+    pub deadline: Instant,
+}
+
+pub struct Heartbeat {
+    pub interval: Duration,
+    pub message: Option<String>, //application-level keepalive sent along with each ping
+}
+
 pub struct WebSocket {
     tx: Sender<String>,
+    status_tx: Sender<ConnectionEvent>,
     socket: tungstenite::WebSocket<MaybeTlsStream<TcpStream>>,
+    recorder: Option<Recorder>,
+    heartbeat: Option<Heartbeat>,
+    last_heartbeat: Instant,
+    ping_sent_at: Option<Instant>, //`Some` while waiting for a pong
 }
 
 impl WebSocket {
     pub fn new(
         tx: Sender<String>,
+        status_tx: Sender<ConnectionEvent>,
         url: &str,
         on_open_message: Option<&str>,
+        recorder: Option<Recorder>,
+        heartbeat: Option<Heartbeat>,
     ) -> Result<Self, Box<dyn Error>> {
         let (mut socket, _response) = tungstenite::connect(url)?;
 
@@ -19,26 +54,107 @@ impl WebSocket {
             socket.write_message(message)?;
         }
 
-        Ok(Self { tx, socket })
+        //Without a read timeout, `read_message()` blocks until the next frame arrives and we cannot send anything in the meantime.
+        match socket.get_ref() {
+            MaybeTlsStream::Plain(s) => s.set_read_timeout(Some(POLL_INTERVAL))?,
+            MaybeTlsStream::NativeTls(s) => s.get_ref().set_read_timeout(Some(POLL_INTERVAL))?,
+            _ => (),
+        }
+
+        Ok(Self {
+            tx,
+            status_tx,
+            socket,
+            recorder,
+            heartbeat,
+            last_heartbeat: Instant::now(),
+            ping_sent_at: None,
+        })
+    }
+
+    fn send_heartbeat_if_due(&mut self) -> Result<(), Box<dyn Error>> {
+        let heartbeat = match &self.heartbeat {
+            None => return Ok(()),
+            Some(h) => h,
+        };
+        if (self.last_heartbeat.elapsed() < heartbeat.interval) {
+            return Ok(());
+        }
+        //The previous ping has been left unanswered for a whole interval, so we regard the connection as dead even though no error is reported.
+        if (self.ping_sent_at.is_some()) {
+            return Err(format!(
+                "no pong received within {}ms",
+                heartbeat.interval.as_millis()
+            )
+            .into());
+        }
+        if let Some(s) = &heartbeat.message {
+            self.socket.write_message(Message::text(s))?;
+        }
+        self.socket.write_message(Message::Ping(vec![]))?;
+        self.last_heartbeat = Instant::now();
+        self.ping_sent_at = Some(self.last_heartbeat);
+        Ok(())
     }
 
-    pub fn read_loop(&mut self) -> Result<(), Box<dyn Error>> {
+    //receives frames until the connection is lost, sending the messages arriving at `outgoing` in the meantime
+    //This returns `Ok(())` only when the client is shutting down (i.e. the other ends of the channels have been dropped),
+    // and an error otherwise, including when the server closes the connection.
+    pub fn read_loop(&mut self, outgoing: &Receiver<Outgoing>) -> Result<(), Box<dyn Error>> {
         loop {
+            loop {
+                match outgoing.try_recv() {
+                    Ok(o) => {
+                        if (Instant::now() > o.deadline) {
+                            continue;
+                        }
+                        let result = self.socket.write_message(Message::text(o.frame));
+                        let _ = o
+                            .result_tx
+                            .send(result.as_ref().map_err(|e| e.to_string()).copied());
+                        result?;
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return Ok(()),
+                }
+            }
+            self.send_heartbeat_if_due()?;
             match self.socket.read_message() {
+                Err(tungstenite::Error::Io(e))
+                    if (e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut) =>
+                {
+                    continue
+                }
                 Err(e) => return Err(e.into()),
                 Ok(m) => match m {
                     Message::Text(s) => {
-                        self.tx.send(s)?;
+                        if let Some(recorder) = &self.recorder {
+                            recorder
+                                .record(&s)
+                                .unwrap_or_else(|e| error!("Failed to record a frame: {}", e));
+                        }
+                        if (self.tx.send(s).is_err()) {
+                            return Ok(());
+                        }
                     }
                     Message::Binary(_) => (),
                     Message::Ping(_) => {
-                        // println!("ping");
-                        // self.socket.write_message(Message::Pong(vec![]))?;
+                        //`tungstenite` has already queued the pong reply; we just flush it.
+                        self.socket.write_pending()?;
                     }
-                    Message::Pong(_) => (),
-                    Message::Close(_) => {
+                    Message::Pong(_) => {
+                        if let Some(t) = self.ping_sent_at.take() {
+                            let event = ConnectionEvent::Latency(t.elapsed());
+                            if (self.status_tx.send(event).is_err()) {
+                                return Ok(());
+                            }
+                        }
+                    }
+                    //Spoon sometimes closes the connection in the middle of a live, so this is not the end.
+                    Message::Close(frame) => {
                         let _ = self.socket.close(None);
-                        return Ok(());
+                        return Err(format!("closed by the server: {:?}", frame).into());
                     }
                     Message::Frame(_) => (),
                 },
@@ -46,3 +162,70 @@ impl WebSocket {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, sync::mpsc, thread};
+
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        //a server which just reads messages (replying to pings automatically) and forwards text messages
+        let (server_tx, server_rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut socket = tungstenite::accept(listener.accept().unwrap().0).unwrap();
+            while let Ok(m) = socket.read_message() {
+                if let Message::Text(s) = m {
+                    server_tx.send(s).unwrap();
+                }
+            }
+        });
+
+        let (tx, _rx) = mpsc::channel();
+        let (outgoing_tx, outgoing_rx) = mpsc::channel();
+        let (status_tx, status_rx) = mpsc::channel();
+        let mut ws = WebSocket::new(
+            tx,
+            status_tx,
+            &url,
+            Some("hello"),
+            None,
+            Some(Heartbeat {
+                interval: Duration::from_millis(200),
+                message: Some("keepalive".to_string()),
+            }),
+        )
+        .unwrap();
+        thread::spawn(move || {
+            let _ = ws.read_loop(&outgoing_rx);
+        });
+
+        let timeout = Duration::from_secs(3);
+        assert_eq!("hello", server_rx.recv_timeout(timeout).unwrap());
+        let (result_tx, result_rx) = mpsc::channel();
+        let outgoing = |frame: &str, deadline: Instant| Outgoing {
+            frame: frame.to_string(),
+            result_tx: result_tx.clone(),
+            deadline,
+        };
+        //expired
+        outgoing_tx
+            .send(outgoing("stale", Instant::now() - Duration::from_secs(1)))
+            .unwrap();
+        outgoing_tx
+            .send(outgoing("world", Instant::now() + timeout))
+            .unwrap();
+        assert_eq!(Ok(()), result_rx.recv_timeout(timeout).unwrap());
+        assert_eq!("world", server_rx.recv_timeout(timeout).unwrap());
+        assert_eq!("keepalive", server_rx.recv_timeout(timeout).unwrap());
+        match status_rx.recv_timeout(timeout).unwrap() {
+            ConnectionEvent::Latency(d) => assert!(d < timeout),
+            e => panic!("unexpected event: {:?}", e),
+        }
+    }
+}